@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use crate::bulk::Outcome;
+use crate::git;
+
+const ORIGIN: &str = "origin";
+
+/// Creates branch `name` from the default branch of the repository at `path` and checks it out
+pub fn create(path: &Path, name: &str) -> Outcome {
+    if git::ref_exists(path, &local_ref(name)) {
+        return Outcome::Skipped(format!("branch {} already exists", name));
+    }
+
+    let default = match git::default_branch(path) {
+        Ok(Some(default)) => default,
+        Ok(None) => return Outcome::Failed("unable to determine the default branch".to_string()),
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+    let start = if git::ref_exists(path, &local_ref(&default)) {
+        default
+    } else {
+        format!("{}/{}", ORIGIN, default)
+    };
+
+    match git::create_branch(path, name, &start) {
+        Ok(()) => Outcome::Done(format!("created {} from {}", name, start)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+/// Checks out existing branch `name` (local or on `origin`) in the repository at `path`
+pub fn switch(path: &Path, name: &str) -> Outcome {
+    if git::current_branch(path).is_ok_and(|current| current.eq(name)) {
+        return Outcome::Skipped(format!("already on {}", name));
+    }
+    let remote_ref = format!("refs/remotes/{}/{}", ORIGIN, name);
+    if !git::ref_exists(path, &local_ref(name)) && !git::ref_exists(path, &remote_ref) {
+        return Outcome::Skipped(format!("branch {} does not exist", name));
+    }
+
+    match git::checkout_in(path, name) {
+        Ok(()) => Outcome::Done(format!("switched to {}", name)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+fn local_ref(branch: &str) -> String {
+    format!("refs/heads/{}", branch)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    /// Repository `origin` with `develop` branch and its clone `local` on `develop`
+    fn init_cloned() -> (TempDir, PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let origin = temp_dir.path().join("origin");
+        fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "-b", "main"]);
+        git(&origin, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "init"]);
+        git(&origin, &["branch", "develop"]);
+        git(&origin, &["branch", "feature/remote"]);
+        git(temp_dir.path(), &["clone", "-b", "develop", "origin", "local"]);
+        let local = temp_dir.path().join("local");
+        (temp_dir, local)
+    }
+
+    #[test]
+    fn should_create_from_default_branch() {
+        let (_temp_dir, local) = init_cloned();
+
+        let outcome = create(&local, "feature/x");
+        assert_eq!(Outcome::Done("created feature/x from origin/main".to_string()), outcome);
+        assert_eq!("feature/x", git::current_branch(&local).unwrap());
+        assert_eq!(None, git::ahead_behind(&local).unwrap(), "must not track origin/main");
+
+        assert!(matches!(create(&local, "feature/x"), Outcome::Skipped(_)));
+    }
+
+    #[test]
+    fn should_switch_to_existing_branches() {
+        let (_temp_dir, local) = init_cloned();
+
+        assert!(matches!(switch(&local, "develop"), Outcome::Skipped(_)));
+        assert!(matches!(switch(&local, "missing"), Outcome::Skipped(_)));
+
+        fs::create_dir_all(local.join("feature")).unwrap();
+        fs::write(local.join("feature/remote"), "same name as the branch").unwrap();
+        assert!(matches!(switch(&local, "feature/remote"), Outcome::Done(_)));
+        assert_eq!("feature/remote", git::current_branch(&local).unwrap());
+
+        assert!(matches!(switch(&local, "develop"), Outcome::Done(_)));
+        assert_eq!("develop", git::current_branch(&local).unwrap());
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::Write;
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::commands::print_project;
+use crate::directory::Directory;
+use crate::project::Project;
+use crate::ERROR_WRITER;
+
+/// Result of an action executed in a single repository
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Done(String),
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub done: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, outcome: &Outcome) {
+        match outcome {
+            Outcome::Done(_) => self.done += 1,
+            Outcome::Skipped(_) => self.skipped += 1,
+            Outcome::Failed(_) => self.failed += 1,
+        }
+    }
+
+    /// Error when the action failed in any of the repositories
+    pub fn into_result(self) -> Result<()> {
+        if self.failed > 0 {
+            return Err(anyhow!("Failed in {} repositories", self.failed));
+        }
+        Ok(())
+    }
+}
+
+/// Executes `action` in every repository of the `projects`, printing the outcome of each one
+pub fn for_each_repo(
+    projects: &[Project],
+    mut writer: impl Write,
+    mut action: impl FnMut(&Project, &Directory) -> Outcome,
+) -> Summary {
+    let mut summary = Summary::default();
+
+    for project in projects {
+        print_project(project, &mut writer);
+        for repo in project.repos.iter().flatten() {
+            let outcome = action(project, repo);
+            print_outcome(repo, &outcome, &mut writer);
+            summary.add(&outcome);
+        }
+    }
+    summary
+}
+
+pub fn print_outcome(repo: &Directory, outcome: &Outcome, mut writer: impl Write) {
+    match outcome {
+        Outcome::Done(msg) => writeln!(writer, "{} {}: {}", "=>".bright_green(), repo.name.yellow(), msg),
+        Outcome::Skipped(msg) => writeln!(writer, "{} {}: {}", "=>".yellow(), repo.name.yellow(), msg),
+        Outcome::Failed(msg) => writeln!(
+            writer,
+            "{} {}: {} {}",
+            "=>".red(),
+            repo.name.yellow(),
+            "Error".red(),
+            msg
+        ),
+    }
+    .expect(ERROR_WRITER);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn should_print_outcomes_and_count() {
+        let project = Project {
+            name: "Project".to_string(),
+            path: PathBuf::from("/some/path"),
+            repos: Some(vec![
+                Directory::from(PathBuf::from("/some/path/first")),
+                Directory::from(PathBuf::from("/some/path/second")),
+                Directory::from(PathBuf::from("/some/path/third")),
+            ]),
+        };
+
+        let mut result = Vec::new();
+        let summary = for_each_repo(&[project], &mut result, |_, repo| match repo.name.as_str() {
+            "first" => Outcome::Done("ok".to_string()),
+            "second" => Outcome::Skipped("nothing to do".to_string()),
+            _ => Outcome::Failed("broken".to_string()),
+        });
+
+        assert_eq!(Summary { done: 1, skipped: 1, failed: 1 }, summary);
+        assert_eq!(
+            String::from_utf8_lossy(&result),
+            format!(
+                "Project {} found at {:?}\n{} {}: ok\n{} {}: nothing to do\n{} {}: {} broken\n",
+                "Project".bright_green(),
+                PathBuf::from("/some/path"),
+                "=>".bright_green(),
+                "first".yellow(),
+                "=>".yellow(),
+                "second".yellow(),
+                "=>".red(),
+                "third".yellow(),
+                "Error".red()
+            )
+        );
+        assert!(summary.into_result().is_err());
+    }
+}
@@ -1,35 +1,255 @@
 use std::io::{stdin, stdout, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Child;
 
-use anyhow::{Context, Result};
-use clap::Subcommand;
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Subcommand};
 use colored::Colorize;
 
-use crate::{ERROR_WRITER, git};
+use crate::{
+    branching, commit, config, diff, doctor, ERROR_WRITER, git, history, import, inventory,
+    maintain, manifest, push, remote, search, snapshot, stale, stash, usage,
+};
+use crate::bulk::{for_each_repo, print_outcome, Outcome, Summary};
+use crate::config::Config;
 use crate::directory::Directory;
-use crate::git::{GIT_PULL, GIT_STATUS};
+use crate::filter::{apply, RepoFilter};
+use crate::import::ImportFormat;
+use crate::manifest::{Manifest, ManifestFormat};
+use crate::git::{HeadState, GIT_PULL, GIT_STATUS};
 use crate::project::{print_projects, Project, scan};
+use crate::selector::select;
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// List all Git repositories in directory pointed by default ENV variable (DEV_DIR) or given 'path' (option)
-    List,
+    List {
+        /// Show the size of the working tree and '.git' directory of every repository
+        /// and project, the largest first. Files ignored by git, like build output, are
+        /// counted too as they fill the disk as well
+        #[arg(long)]
+        size: bool,
+    },
 
-    /// Execute 'git status' on all repositories for given project 'name'
+    /// Execute 'git status' on all selected repositories
     Status {
-        /// Name of the project to check status (directory with Git repositories,
-        /// which exists in DEFAULT_VAR (DEV_DIR)
-        /// 'all' can be used to execute command for all projects in DEV_DIR
-        name: String,
+        #[command(flatten)]
+        target: Target,
     },
 
-    /// Execute 'git pull' on all repositories for given project 'name'
+    /// Execute 'git pull' on all selected repositories
     Pull {
-        /// Name of the project to pull (directory with Git repositories,
-        /// which exists in DEFAULT_VAR (DEV_DIR)
-        /// 'all' can be used to execute command for all projects in DEV_DIR
-        name: String,
+        #[command(flatten)]
+        target: Target,
+    },
+
+    /// Create the same branch in all selected repositories
+    #[command(subcommand_precedence_over_arg = true)]
+    Branch {
+        #[command(flatten)]
+        target: Target,
+
+        #[command(subcommand)]
+        action: BranchAction,
+    },
+
+    /// Show local branches of all selected repositories with their last commit date,
+    /// upstream tracking state and whether they are merged into the default branch
+    Branches {
+        #[command(flatten)]
+        target: Target,
+    },
+
+    /// Report local and remote branches without commits for a given number of days,
+    /// grouped by repository and author, and optionally delete them.
+    /// Default branches and 'protected_branches' from '.please.toml' are never reported
+    Stale {
+        #[command(flatten)]
+        target: Target,
+
+        /// Age in days of the last commit, after which a branch is considered stale
+        #[arg(long, value_name = "DAYS", default_value_t = stale::DEFAULT_AGE_DAYS)]
+        older_than: u64,
+
+        /// Report only local branches
+        #[arg(long)]
+        local: bool,
+
+        /// Delete reported branches after confirmation, remote ones are deleted on the remote.
+        /// Only branches merged into the default branch are deleted
+        #[arg(long)]
+        delete: bool,
+
+        /// With --delete, delete also branches which are not merged into the default branch
+        #[arg(long, requires = "delete")]
+        force: bool,
+    },
+
+    /// Checkout given branch in all selected repositories, which have it
+    Switch {
+        #[command(flatten)]
+        target: Target,
+
+        /// Name of the branch to checkout to
+        branch: String,
+    },
+
+    /// Commit staged changes in all selected repositories, which have any
+    Commit {
+        #[command(flatten)]
+        target: Target,
+
+        /// Commit message, '{repo}', '{project}' and '{branch}' are replaced for each repository
+        #[arg(short, long)]
+        message: String,
+    },
+
+    /// Push the current branch in all selected repositories with commits ahead of the upstream.
+    /// Branches protected in '.please.toml' are never pushed
+    Push {
+        #[command(flatten)]
+        target: Target,
+
+        /// Push branches without upstream and set it to the same branch on 'origin'
+        #[arg(short = 'u', long)]
+        set_upstream: bool,
+    },
+
+    /// Stash changes in all selected repositories with a shared label and restore them later
+    #[command(subcommand_precedence_over_arg = true)]
+    Stash {
+        #[command(flatten)]
+        target: Target,
+
+        #[command(subcommand)]
+        action: Option<StashAction>,
+    },
+
+    /// Show commits of all selected repositories in a single timeline, the newest first
+    Log {
+        #[command(flatten)]
+        target: Target,
+
+        /// Show only commits newer than given date, e.g. '2.days' or '2024-01-31'
+        #[arg(long, default_value = "1.week")]
+        since: String,
+
+        /// Show only commits of authors matching given pattern
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Show only commits touching given file or directory, can be repeated
+        #[arg(long = "file", value_name = "PATH")]
+        paths: Vec<String>,
+    },
+
+    /// Search tracked files of all selected repositories with 'git grep'.
+    /// Exits with an error when nothing matched
+    Grep {
+        #[command(flatten)]
+        target: Target,
+
+        /// Pattern to search for, a basic regular expression
+        pattern: String,
+
+        /// Ignore case distinctions in the pattern and the files
+        #[arg(short, long)]
+        ignore_case: bool,
+    },
+
+    /// Show changes of all selected repositories followed by a summary per repository
+    Diff {
+        #[command(flatten)]
+        target: Target,
+
+        /// Show changes staged for a commit instead of unstaged ones
+        #[arg(long)]
+        staged: bool,
+
+        /// Show only the number of changed lines per file instead of the full patch
+        #[arg(long)]
+        stat: bool,
+    },
+
+    /// List and edit remotes of all selected repositories
+    #[command(subcommand_precedence_over_arg = true)]
+    Remote {
+        #[command(flatten)]
+        target: Target,
+
+        /// Only show what would be changed
+        #[arg(long, global = true)]
+        dry_run: bool,
+
+        #[command(subcommand)]
+        action: Option<RemoteAction>,
+    },
+
+    /// Run 'git maintenance' and 'git remote prune' in all selected repositories
+    /// and show the size of each '.git' directory before and after
+    Maintain {
+        #[command(flatten)]
+        target: Target,
+
+        /// Run 'git gc --aggressive' and drop all unreachable objects, which takes longer
+        #[arg(long)]
+        aggressive: bool,
+    },
+
+    /// Record branch and commit of repositories and restore them later
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+
+    /// Clone repositories declared in the manifest, which are missing in DEV_DIR
+    /// and report repositories not declared in it
+    Sync {
+        /// Path to the manifest file, 'please-manifest.toml' in DEV_DIR by default
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+    },
+
+    /// Check git, DEV_DIR and the projects found in it, then repositories for missing 'origin',
+    /// upstreams which are not set or gone and remote URLs not matching 'remote_urls'
+    /// in '.please.toml'. Every problem is printed with a suggested fix
+    #[command(mut_arg("names", |names| names.required(false).default_value("all")))]
+    Doctor {
+        #[command(flatten)]
+        target: Target,
+
+        /// Also verify that no objects are missing in repositories, which takes longer
+        #[arg(long)]
+        fsck: bool,
+    },
+
+    /// Write a manifest describing all projects and repositories found in DEV_DIR
+    Export {
+        /// File to write the manifest to, standard output by default
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Format of the manifest, based on the output file extension by default
+        #[arg(short, long, value_enum)]
+        format: Option<ManifestFormat>,
+    },
+
+    /// Convert a repository list of another multi-repo tool into a manifest
+    Import {
+        /// Google 'repo' XML manifest, '.mrconfig' file or 'gita' repos.csv
+        file: PathBuf,
+
+        /// Format of the imported file, based on its name by default
+        #[arg(long, value_enum)]
+        from: Option<ImportFormat>,
+
+        /// File to write the manifest to, standard output by default
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Format of the manifest, based on the output file extension by default
+        #[arg(short, long, value_enum)]
+        format: Option<ManifestFormat>,
     },
 
     /// Checkout to develop > master > main branch and delete previous branch
@@ -37,34 +257,505 @@ pub enum Commands {
     Clean,
 }
 
-pub fn handle_list(path: &Path, writer: impl Write) -> Result<()> {
+#[derive(Subcommand)]
+pub enum BranchAction {
+    /// Create the branch from the default branch of each repository and checkout to it
+    Create {
+        /// Name of the new branch
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RemoteAction {
+    /// List remotes with their URLs (default)
+    List,
+
+    /// Add a remote to every repository, which doesn't have it yet
+    Add {
+        name: String,
+
+        /// URL of the remote, '{repo}' and '{project}' are replaced for each repository
+        url: String,
+    },
+
+    /// Change the URL of an existing remote
+    SetUrl {
+        name: String,
+
+        /// URL of the remote, '{repo}' and '{project}' are replaced for each repository
+        url: String,
+    },
+
+    /// Rename an existing remote
+    Rename {
+        old: String,
+        new: String,
+    },
+
+    /// Replace a part of remote URLs, e.g. after moving repositories to another organization
+    Rewrite {
+        /// Part of the URL to replace and its replacement, e.g.
+        /// 'github.com:old-org=github.com:new-org'. Can be repeated, the first match is applied
+        #[arg(long = "replace", value_name = "OLD=NEW", required = true,
+            value_parser = remote::parse_replacement)]
+        replacements: Vec<(String, String)>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StashAction {
+    /// Stash changes, including untracked files, of every dirty repository (default)
+    Push {
+        /// Label to pop the stashes with later, it can't be used by another stash
+        #[arg(default_value = stash::DEFAULT_LABEL)]
+        label: String,
+    },
+
+    /// Pop the stash with given label in every repository
+    Pop {
+        #[arg(default_value = stash::DEFAULT_LABEL)]
+        label: String,
+    },
+
+    /// List stashes created with 'please stash'
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Save current branch and HEAD commit of all selected repositories
+    Save {
+        /// Name of the snapshot
+        name: String,
+
+        #[command(flatten)]
+        target: Target,
+
+        /// Overwrite an existing snapshot with the same name
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Checkout all repositories of the snapshot to the recorded branch and commit
+    Restore {
+        /// Name of the snapshot
+        name: String,
+    },
+
+    /// List saved snapshots
+    List,
+}
+
+/// Projects and repositories a command is executed for
+#[derive(Args)]
+pub struct Target {
+    /// Names of the projects (directories with Git repositories), which exist in DEFAULT_VAR (DEV_DIR).
+    /// Accepts qualified names ('clients/api'), glob patterns ('svc-*'),
+    /// single repositories ('project:repo') and groups defined in '.please.toml'.
+    /// 'all' can be used to execute command for all projects in DEV_DIR
+    #[arg(required = true)]
+    pub names: Vec<String>,
+
+    /// Patterns of projects, repositories or 'project:repo' pairs to leave out
+    #[arg(short, long)]
+    pub exclude: Vec<String>,
+
+    #[command(flatten)]
+    pub filter: RepoFilter,
+}
+
+pub fn handle_list(path: &Path, size: bool, writer: impl Write) -> Result<()> {
     println!("Scanning in path {:?}", path);
     let projects = scan(path).with_context(|| "Scanning for projects failed")?;
-    print_projects(projects, writer);
+    if size {
+        usage::print_usage(&usage::measure(&projects), writer);
+    } else {
+        print_projects(projects, writer);
+    }
     Ok(())
 }
 
-pub fn handle_status(path: &Path, name: &String) -> Result<()> {
-    execute_git_cmd(path, name, GIT_STATUS)
+pub fn handle_status(path: &Path, target: &Target) -> Result<()> {
+    execute_git_cmd(path, target, GIT_STATUS)
 }
 
-pub fn handle_pull(path: &Path, name: &String) -> Result<()> {
-    execute_git_cmd(path, name, GIT_PULL)
+pub fn handle_pull(path: &Path, target: &Target) -> Result<()> {
+    execute_git_cmd(path, target, GIT_PULL)
 }
 
-fn execute_git_cmd(path: &Path, name: &String, git_cmd: &str) -> Result<()> {
-    let projects = scan(path).with_context(|| "Scanning for projects failed")?;
+pub fn handle_branch(path: &Path, target: &Target, action: &BranchAction) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    match action {
+        BranchAction::Create { name } => {
+            for_each_repo(&projects, stdout(), |_, repo| branching::create(&repo.path, name))
+        }
+    }
+    .into_result()
+}
 
-    if "all".eq_ignore_ascii_case(name) {
-        projects
-            .iter()
-            .for_each(|project| for_project(git_cmd, project, &mut stdout()))
+pub fn handle_branches(path: &Path, target: &Target) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let mut repos = Vec::new();
+    for project in &projects {
+        for repo in project.repos.iter().flatten() {
+            let name = project.repo_name(path, repo);
+            match inventory::collect(&repo.path, &name) {
+                Ok(branches) => repos.push(branches),
+                Err(e) => eprintln!("{} {}: {}", "Error".red(), name.yellow(), e),
+            }
+        }
+    }
+    inventory::print_matrix(&repos, stdout());
+    Ok(())
+}
+
+pub fn handle_stale(
+    path: &Path,
+    target: &Target,
+    older_than: u64,
+    local: bool,
+    delete: bool,
+    force: bool,
+) -> Result<()> {
+    let config = config::load(path)?;
+    let projects = selected_projects(path, target, &config)?;
+    let cutoff = stale::cutoff(older_than);
+    let mut branches = Vec::new();
+    for project in &projects {
+        for repo in project.repos.iter().flatten() {
+            let name = project.repo_name(path, repo);
+            let is_protected = |branch: &str| config.is_protected(project, path, branch);
+            match stale::find(&repo.path, &name, cutoff, !local, is_protected) {
+                Ok(found) => branches.extend(found),
+                Err(e) => eprintln!("{} {}: {}", "Error".red(), name.yellow(), e),
+            }
+        }
+    }
+
+    if branches.is_empty() {
+        println!("No branches older than {} days", older_than);
+        return Ok(());
+    }
+    stale::print_report(&branches, stale::cutoff(0), stdout());
+    if !delete {
+        return Ok(());
+    }
+
+    let deleted: Vec<_> = branches.iter().filter(|b| b.merged || force).collect();
+    if deleted.is_empty() {
+        println!("No merged branches to delete, use --force to delete the others");
+        return Ok(());
+    }
+    let remote = deleted.iter().filter(|b| b.remote.is_some()).count();
+    println!(
+        "{} branches will be deleted, {} of them on their remotes. {}",
+        deleted.len().to_string().bright_red(),
+        remote.to_string().bright_red(),
+        if force {
+            "Branches which are not merged are deleted too"
+        } else {
+            "Branches which are not merged are kept, use --force to delete them"
+        }
+    );
+    println!("Continue? (y / N and hit Enter)");
+    if !user_confirmed(&get_user_input()) {
+        println!("Aborting");
+        return Ok(());
+    }
+    let mut summary = Summary::default();
+    for branch in &branches {
+        let outcome = stale::delete(branch, force);
+        let repo = Directory { name: branch.repo.clone(), path: branch.path.clone() };
+        print_outcome(&repo, &outcome, stdout());
+        summary.add(&outcome);
+    }
+    summary.into_result()
+}
+
+pub fn handle_switch(path: &Path, target: &Target, branch: &str) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    for_each_repo(&projects, stdout(), |_, repo| branching::switch(&repo.path, branch))
+        .into_result()
+}
+
+pub fn handle_commit(path: &Path, target: &Target, message: &str) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let summary = for_each_repo(&projects, stdout(), |project, repo| {
+        let branch = git::current_branch(&repo.path).unwrap_or_default();
+        let message = commit::render(message, &repo.name, &project.name, &branch);
+        commit::commit(&repo.path, &message)
+    });
+    println!(
+        "Committed in {} repositories, nothing staged in {}",
+        summary.done.to_string().bright_green(),
+        summary.skipped.to_string().yellow()
+    );
+    summary.into_result()
+}
+
+pub fn handle_push(path: &Path, target: &Target, set_upstream: bool) -> Result<()> {
+    let config = config::load(path)?;
+    let projects = selected_projects(path, target, &config)?;
+    for_each_repo(&projects, stdout(), |project, repo| {
+        push::push(&repo.path, set_upstream, |branch| config.is_protected(project, path, branch))
+    })
+    .into_result()
+}
+
+pub fn handle_stash(path: &Path, target: &Target, action: &Option<StashAction>) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let default_push = StashAction::Push { label: stash::DEFAULT_LABEL.to_string() };
+    match action.as_ref().unwrap_or(&default_push) {
+        StashAction::Push { label } => {
+            for_each_repo(&projects, stdout(), |_, repo| stash::push(&repo.path, label))
+        }
+        StashAction::Pop { label } => {
+            for_each_repo(&projects, stdout(), |_, repo| stash::pop(&repo.path, label))
+        }
+        StashAction::List => for_each_repo(&projects, stdout(), |_, repo| stash::list(&repo.path)),
+    }
+    .into_result()
+}
+
+pub fn handle_remote(
+    path: &Path,
+    target: &Target,
+    action: &Option<RemoteAction>,
+    dry_run: bool,
+) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    for_each_repo(&projects, stdout(), |project, repo| {
+        match action.as_ref().unwrap_or(&RemoteAction::List) {
+            RemoteAction::List => remote::list(&repo.path),
+            RemoteAction::Add { name, url } => {
+                let url = remote::render(url, &repo.name, &project.name);
+                remote::add(&repo.path, name, &url, dry_run)
+            }
+            RemoteAction::SetUrl { name, url } => {
+                let url = remote::render(url, &repo.name, &project.name);
+                remote::set_url(&repo.path, name, &url, dry_run)
+            }
+            RemoteAction::Rename { old, new } => remote::rename(&repo.path, old, new, dry_run),
+            RemoteAction::Rewrite { replacements } => {
+                remote::rewrite(&repo.path, replacements, dry_run)
+            }
+        }
+    })
+    .into_result()
+}
+
+pub fn handle_snapshot(path: &Path, action: &SnapshotAction) -> Result<()> {
+    match action {
+        SnapshotAction::Save { name, target, force } => {
+            let projects = selected_projects(path, target, &config::load(path)?)?;
+            snapshot::save(path, &projects, name, *force, stdout())?;
+            println!("Snapshot {} saved", name.bright_green());
+            Ok(())
+        }
+        SnapshotAction::Restore { name } => {
+            let saved = snapshot::load(path, name)?;
+            snapshot::restore(path, &saved, stdout()).into_result()
+        }
+        SnapshotAction::List => {
+            for name in snapshot::list(path)? {
+                println!("  - {}", name.bright_green());
+            }
+            Ok(())
+        }
+    }
+}
+
+pub fn handle_log(path: &Path, target: &Target, filter: history::LogFilter) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let (entries, warnings) = history::timeline(path, &projects, &filter);
+    for warning in warnings {
+        eprintln!("{} {}", "Warning:".yellow(), warning);
+    }
+    history::print_timeline(&entries, stdout());
+    Ok(())
+}
+
+pub fn handle_grep(path: &Path, target: &Target, pattern: &str, ignore_case: bool) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let mut found = 0;
+    let mut failed = 0;
+    for project in &projects {
+        for repo in project.repos.iter().flatten() {
+            let name = project.repo_name(path, repo);
+            match search::grep(&repo.path, pattern, ignore_case) {
+                Ok(matches) => {
+                    found += matches.len();
+                    let prefix = repo.path.strip_prefix(path).unwrap_or(&repo.path);
+                    search::print_matches(&prefix.to_string_lossy(), &matches, stdout());
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("{} {}: {}", "Error".red(), name.yellow(), e);
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(anyhow!("Failed in {} repositories", failed))
+    } else if found == 0 {
+        Err(anyhow!("No matches found"))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn handle_diff(path: &Path, target: &Target, staged: bool, stat: bool) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let mut stats = Vec::new();
+    let mut failed = 0;
+    for project in &projects {
+        for repo in project.repos.iter().flatten() {
+            let name = project.repo_name(path, repo);
+            let files = match diff::numstat(&repo.path, staged) {
+                Ok(files) if files.is_empty() => continue,
+                Ok(files) => files,
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("{} {}: {}", "Error".red(), name.yellow(), e);
+                    continue;
+                }
+            };
+            let prefix = repo.path.strip_prefix(path).unwrap_or(&repo.path).to_string_lossy();
+            if stat {
+                diff::print_stat(&prefix, &files, stdout());
+            } else {
+                match diff::patch(&repo.path, staged, &prefix) {
+                    Ok(patch) => println!("{}", patch),
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("{} {}: {}", "Error".red(), name.yellow(), e);
+                    }
+                }
+            }
+            stats.push((name, diff::DiffStat::of(&files)));
+        }
+    }
+
+    println!();
+    diff::print_summary(&stats, stdout());
+    if failed > 0 {
+        Err(anyhow!("Failed in {} repositories", failed))
     } else {
-        let project = projects
-            .iter()
-            .find(|p| p.name.eq_ignore_ascii_case(name))
-            .with_context(|| format!("Project with given name '{}' was not found", &name.red()))?;
-        for_project(git_cmd, project, &mut stdout());
+        Ok(())
+    }
+}
+
+pub fn handle_maintain(path: &Path, target: &Target, aggressive: bool) -> Result<()> {
+    let projects = selected_projects(path, target, &config::load(path)?)?;
+    let mut sizes = Vec::new();
+    let summary = for_each_repo(&projects, stdout(), |_, repo| {
+        match maintain::maintain(&repo.path, aggressive) {
+            Ok((repo_sizes, warnings)) => {
+                sizes.push((repo.name.clone(), repo_sizes));
+                let described = maintain::describe(&repo_sizes);
+                if warnings.is_empty() {
+                    Outcome::Done(described)
+                } else {
+                    let warnings = warnings.join(", ");
+                    Outcome::Failed(format!("{}, pruning failed: {}", described, warnings))
+                }
+            }
+            Err(e) => Outcome::Failed(e.to_string()),
+        }
+    });
+
+    println!();
+    maintain::print_summary(&sizes, stdout());
+    summary.into_result()
+}
+
+fn execute_git_cmd(path: &Path, target: &Target, git_cmd: &str) -> Result<()> {
+    selected_projects(path, target, &config::load(path)?)?
+        .iter()
+        .for_each(|project| for_project(git_cmd, project, &mut stdout()));
+    Ok(())
+}
+
+/// Scans `path` and narrows down the projects to the `target`
+pub(crate) fn selected_projects(
+    path: &Path,
+    target: &Target,
+    config: &Config,
+) -> Result<Vec<Project>> {
+    let projects = scan(path).with_context(|| "Scanning for projects failed")?;
+    let selected = select(projects, path, config, &target.names, &target.exclude)?;
+    Ok(apply(selected, &target.filter))
+}
+
+/// Unlike other commands, runs also without `path`, when the DEV_DIR variable `var` is not defined
+pub fn handle_doctor(
+    path: Option<&Path>,
+    var: &str,
+    target: &Target,
+    fsck: bool,
+) -> Result<()> {
+    match doctor::diagnose(path, var, target, fsck, stdout())? {
+        0 => {
+            println!("No problems found");
+            Ok(())
+        }
+        problems => Err(anyhow!("Found {} problems", problems)),
+    }
+}
+
+pub fn handle_sync(path: &Path, manifest_path: &Option<PathBuf>, writer: impl Write) -> Result<()> {
+    let manifest_path = manifest_path
+        .clone()
+        .unwrap_or_else(|| path.join(manifest::MANIFEST_FILE));
+    let manifest = manifest::load(&manifest_path)?;
+    manifest::sync(path, &manifest, writer)
+}
+
+pub fn handle_export(
+    path: &Path,
+    output: &Option<PathBuf>,
+    format: &Option<ManifestFormat>,
+) -> Result<()> {
+    let projects = scan(path).with_context(|| "Scanning for projects failed")?;
+    let (exported, warnings) = manifest::export(path, &projects);
+    write_manifest(&exported, &warnings, output, format)
+}
+
+pub fn handle_import(
+    path: &Path,
+    file: &Path,
+    from: &Option<ImportFormat>,
+    output: &Option<PathBuf>,
+    format: &Option<ManifestFormat>,
+) -> Result<()> {
+    let (imported, warnings) = import::import_file(file, *from, path)?;
+    write_manifest(&imported, &warnings, output, format)
+}
+
+/// Writes the manifest to the `output` file or standard output, warnings go to standard error
+fn write_manifest(
+    manifest: &Manifest,
+    warnings: &[String],
+    output: &Option<PathBuf>,
+    format: &Option<ManifestFormat>,
+) -> Result<()> {
+    for warning in warnings {
+        eprintln!("{} {}", "Warning:".yellow(), warning);
+    }
+
+    let format = format.unwrap_or_else(|| {
+        output.as_deref().map(ManifestFormat::from_path).unwrap_or_default()
+    });
+    let content = manifest::to_string(manifest, format)?;
+    match output {
+        Some(file) => {
+            std::fs::write(file, content)
+                .with_context(|| format!("Failed to write manifest to {:?}", file))?;
+            println!("Manifest written to {:?}", file);
+        }
+        None => print!("{}", content),
     }
     Ok(())
 }
@@ -74,13 +765,23 @@ fn for_project(arg: &str, project: &Project, mut writer: impl Write) {
 
     if let Some(repos) = &project.repos {
         repos.iter().for_each(|repo| {
-            let cmd = git::custom_cwd_cmd(arg, repo.path.as_path());
-            print_repository(repo, cmd, &mut writer);
+            let state = git::head_state(&repo.path).ok().filter(|s| !s.is_branch());
+            match state {
+                Some(state) if arg == GIT_PULL => {
+                    let reason = format!("not pulling, {}", describe_state(&state));
+                    let outcome = Outcome::Skipped(reason);
+                    print_outcome(repo, &outcome, &mut writer);
+                }
+                state => {
+                    let cmd = git::custom_cwd_cmd(arg, repo.path.as_path());
+                    print_repository(repo, state, cmd, &mut writer);
+                }
+            }
         });
     }
 }
 
-fn print_project(project: &Project, mut writer: impl Write) {
+pub(crate) fn print_project(project: &Project, mut writer: impl Write) {
     writeln!(
         &mut writer,
         "Project {} found at {:?}",
@@ -89,21 +790,31 @@ fn print_project(project: &Project, mut writer: impl Write) {
     ).expect(ERROR_WRITER);
 }
 
-fn print_repository(repo: &Directory, cmd: Child, mut writer: impl Write) {
+/// Prints the output of `cmd`, with HEAD `state` next to the repository name when given
+fn print_repository(repo: &Directory, state: Option<HeadState>, cmd: Child, mut writer: impl Write) {
     let cmd_output = cmd.wait_with_output().unwrap();
+    let state = match state {
+        Some(state) if state.in_progress() => {
+            format!(" [{}]", describe_state(&state)).bright_red().bold().to_string()
+        }
+        Some(state) => format!(" [{}]", state).bright_red().to_string(),
+        None => String::new(),
+    };
     match cmd_output.status.code() {
         Some(0) => writeln!(
             writer,
-            "{} {}: {}",
+            "{} {}{}: {}",
             "=>".bright_green(),
             repo.name.yellow(),
+            state,
             String::from_utf8_lossy(&cmd_output.stdout)
         ).expect(ERROR_WRITER),
         Some(code) => writeln!(
             writer,
-            "{} {}: {} {}",
+            "{} {}{}: {} {}",
             "=>".red(),
             repo.name.yellow(),
+            state,
             "Error".red(),
             code
         ).expect(ERROR_WRITER),
@@ -112,8 +823,14 @@ fn print_repository(repo: &Directory, cmd: Child, mut writer: impl Write) {
 }
 
 pub fn handle_clean() -> Result<()> {
-    let current = git::get_curr_branch()?;
-    let branches = git::get_branches()?;
+    let current = match git::head_state(Path::new("."))? {
+        HeadState::Branch(branch) => branch,
+        state => {
+            println!("Skipping clean, {}", describe_state(&state));
+            return Ok(());
+        }
+    };
+    let branches = git::get_branches()?.into_iter().map(|b| b.name).collect();
     clean(current, branches, stdout())
 }
 
@@ -152,6 +869,14 @@ fn clean(current: String, branches: Vec<String>, mut writer: impl Write) -> Resu
     }
 }
 
+/// HEAD state with the command to abort the operation in progress, if any
+fn describe_state(state: &HeadState) -> String {
+    match state.abort_command() {
+        Some(abort) => format!("{}, finish it or run '{}'", state, abort),
+        None => state.to_string(),
+    }
+}
+
 fn get_user_input() -> String {
     let mut input = String::new();
     stdin().read_line(&mut input)
@@ -165,22 +890,14 @@ fn user_confirmed(input: &str) -> bool {
 }
 
 fn determine_target(branches: Vec<String>) -> Option<String> {
-    let develop = "develop".to_string();
-    if branches.contains(&develop) { return Some(develop) }
-
-    let main = "main".to_string();
-    if branches.contains(&main) { return Some(main) }
-
-    let master = "master".to_string();
-    if branches.contains(&master) { return Some(master) };
-
-    None
+    git::DEFAULT_BRANCHES
+        .iter()
+        .find(|target| branches.iter().any(|b| b.eq(*target)))
+        .map(|target| target.to_string())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
-
     use tempfile::{tempdir, tempdir_in};
 
     use super::*;
@@ -188,13 +905,13 @@ mod tests {
     #[test]
     fn should_print_git_error_128() {
         let temp_dir = tempdir().unwrap();
-        let temp_sub_dir = tempdir_in(&temp_dir.path()).unwrap();
+        let temp_sub_dir = tempdir_in(temp_dir.path()).unwrap();
         let project = Project {
             name: "Project".to_string(),
-            path: temp_dir.into_path(),
+            path: temp_dir.path().to_path_buf(),
             repos: Some(vec![Directory {
                 name: "Repo".to_string(),
-                path: temp_sub_dir.into_path(),
+                path: temp_sub_dir.path().to_path_buf(),
             }]),
         };
 
@@ -214,6 +931,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_skip_pull_without_branch() {
+        let temp_dir = tempdir().unwrap();
+        let repo_path = temp_dir.path().join("repo");
+        std::fs::create_dir_all(&repo_path).unwrap();
+        git::query_cmd(&["init", "-b", "main"], &repo_path);
+        git::query_cmd(&["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "init"], &repo_path);
+        git::query_cmd(&["checkout", "-q", "--detach"], &repo_path);
+        let project = Project {
+            name: "Project".to_string(),
+            path: temp_dir.path().to_path_buf(),
+            repos: Some(vec![Directory::from(repo_path)]),
+        };
+
+        let mut result = Vec::new();
+        for_project(GIT_PULL, &project, &mut result);
+
+        let result = String::from_utf8_lossy(&result);
+        assert!(result.contains("HEAD detached at"), "{}", result);
+        assert!(result.contains("not pulling"), "{}", result);
+    }
+
     #[test]
     fn should_print_project() {
         let project = Project {
@@ -241,12 +981,16 @@ mod tests {
     #[test]
     fn test_execute_git_cmd_project_not_found() {
         let temp_dir = tempdir().unwrap();
-        let _temp_sub_dir = tempdir_in(&temp_dir.path()).unwrap();
+        let _temp_sub_dir = tempdir_in(temp_dir.path()).unwrap();
         let path = temp_dir.path().to_path_buf();
-        let name = "nonexistent".to_string();
+        let target = Target {
+            names: vec!["nonexistent".to_string()],
+            exclude: Vec::new(),
+            filter: RepoFilter::default(),
+        };
         let git_cmd = "status";
 
-        let result = execute_git_cmd(&path, &name, git_cmd);
+        let result = execute_git_cmd(&path, &target, git_cmd);
         assert!(result.is_err());
     }
 
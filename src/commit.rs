@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use crate::bulk::Outcome;
+use crate::git;
+
+/// Fills `{repo}`, `{project}` and `{branch}` placeholders of the message `template`
+pub fn render(template: &str, repo: &str, project: &str, branch: &str) -> String {
+    template
+        .replace("{repo}", repo)
+        .replace("{project}", project)
+        .replace("{branch}", branch)
+}
+
+/// Commits staged changes of the repository at `path`, skipping it when nothing is staged
+pub fn commit(path: &Path, message: &str) -> Outcome {
+    match git::has_staged(path) {
+        Ok(false) => return Outcome::Skipped("nothing staged".to_string()),
+        Err(e) => return Outcome::Failed(e.to_string()),
+        Ok(true) => {}
+    }
+
+    match git::commit(path, message) {
+        Ok(()) => Outcome::Done(format!(
+            "committed {}",
+            git::head_commit(path).unwrap_or_default()
+        )),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn should_render_template() {
+        assert_eq!(
+            "api (clients) on main: bump {version}",
+            render("{repo} ({project}) on {branch}: bump {version}", "api", "clients", "main")
+        );
+    }
+
+    #[test]
+    fn should_commit_only_staged_changes() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        git(path, &["config", "user.name", "Tester"]);
+        git(path, &["config", "user.email", "tester@example.com"]);
+
+        fs::write(path.join("unstaged.txt"), "content").unwrap();
+        assert_eq!(Outcome::Skipped("nothing staged".to_string()), commit(path, "message"));
+
+        git(path, &["add", "unstaged.txt"]);
+        assert!(matches!(commit(path, "first commit"), Outcome::Done(_)));
+        assert_eq!("first commit", git(path, &["log", "-1", "--format=%s"]));
+        assert_eq!(Outcome::Skipped("nothing staged".to_string()), commit(path, "message"));
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::project::Project;
+use crate::selector::wildcard_match;
+
+pub const CONFIG_FILE: &str = ".please.toml";
+
+/// Optional configuration read from `.please.toml` in the scanned directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Named sets of selectors (`svc-*`, `clients:api`), usable instead of a project name
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
+
+    /// Settings of projects matching the key (name, qualified name or a glob pattern)
+    #[serde(default)]
+    pub projects: BTreeMap<String, ProjectConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Branch names or patterns `push` refuses to push to
+    #[serde(default)]
+    pub protected_branches: Vec<String>,
+
+    /// Patterns the URL of `origin` is expected to match, e.g. `git@github.com:my-org/*`
+    #[serde(default)]
+    pub remote_urls: Vec<String>,
+}
+
+pub fn load(root: &Path) -> Result<Config> {
+    let path = root.join(CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(Config::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file at {:?}", path))?;
+    parse(&content).with_context(|| format!("Invalid config file at {:?}", path))
+}
+
+pub fn parse(content: &str) -> Result<Config> {
+    Ok(toml::from_str(content)?)
+}
+
+impl Config {
+    /// Replaces group names in `names` with the selectors of those groups
+    pub fn expand(&self, names: &[String]) -> Vec<String> {
+        names
+            .iter()
+            .flat_map(|name| match self.group(name) {
+                Some(selectors) => selectors.clone(),
+                None => vec![name.clone()],
+            })
+            .collect()
+    }
+
+    /// Settings of all entries in `projects` matching given project
+    pub fn for_project<'a>(&'a self, project: &Project, root: &Path) -> Vec<&'a ProjectConfig> {
+        let qualified = project.qualified_name(root);
+        self.projects
+            .iter()
+            .filter(|(key, _)| {
+                wildcard_match(key, &project.name) || wildcard_match(key, &qualified)
+            })
+            .map(|(_, config)| config)
+            .collect()
+    }
+
+    pub fn is_protected(&self, project: &Project, root: &Path, branch: &str) -> bool {
+        self.for_project(project, root)
+            .iter()
+            .flat_map(|config| &config.protected_branches)
+            .any(|pattern| wildcard_match(pattern, branch))
+    }
+
+    /// Patterns of remote URLs of all entries matching given project
+    pub fn remote_urls(&self, project: &Project, root: &Path) -> Vec<String> {
+        self.for_project(project, root)
+            .iter()
+            .flat_map(|config| config.remote_urls.iter().cloned())
+            .collect()
+    }
+
+    fn group(&self, name: &str) -> Option<&Vec<String>> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group.eq_ignore_ascii_case(name))
+            .map(|(_, selectors)| selectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn should_parse_groups() {
+        let config = parse(
+            r#"
+            [groups]
+            backend = ["svc-*", "clients:api"]
+            infra = ["terraform"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(2, config.groups.len());
+        assert_eq!(vec!["svc-*", "clients:api"], config.groups["backend"]);
+    }
+
+    #[test]
+    fn should_reject_unknown_fields() {
+        assert!(parse("[unknown]\nkey = 1").is_err());
+    }
+
+    #[test]
+    fn should_expand_groups() {
+        let config = parse("[groups]\nbackend = [\"svc-*\", \"clients:api\"]").unwrap();
+        let names = vec!["Backend".to_string(), "frontend".to_string()];
+
+        assert_eq!(vec!["svc-*", "clients:api", "frontend"], config.expand(&names));
+    }
+
+    #[test]
+    fn should_match_protected_branches() {
+        let config = parse(
+            r#"
+            [projects."*"]
+            protected_branches = ["main"]
+
+            [projects."clients/api"]
+            protected_branches = ["release/*"]
+            "#,
+        )
+        .unwrap();
+        let root = Path::new("/dev");
+        let api = Project {
+            name: "api".to_string(),
+            path: root.join("clients/api"),
+            repos: None,
+        };
+        let web = Project {
+            name: "web".to_string(),
+            path: root.join("web"),
+            repos: None,
+        };
+
+        assert_eq!(2, config.for_project(&api, root).len());
+        assert!(config.is_protected(&api, root, "main"));
+        assert!(config.is_protected(&api, root, "release/1.0"));
+        assert!(config.is_protected(&web, root, "main"));
+        assert!(!config.is_protected(&web, root, "release/1.0"));
+        assert!(!config.is_protected(&web, root, "feature/x"));
+    }
+
+    #[test]
+    fn should_collect_remote_urls() {
+        let config = parse(
+            r#"
+            [projects."*"]
+            remote_urls = ["git@github.com:my-org/*"]
+
+            [projects.api]
+            remote_urls = ["https://github.com/my-org/*"]
+            "#,
+        )
+        .unwrap();
+        let root = Path::new("/dev");
+        let api = Project {
+            name: "api".to_string(),
+            path: root.join("api"),
+            repos: None,
+        };
+
+        assert_eq!(
+            vec!["git@github.com:my-org/*", "https://github.com/my-org/*"],
+            config.remote_urls(&api, root)
+        );
+    }
+
+    #[test]
+    fn should_load_default_without_file() {
+        let temp_dir = tempdir().unwrap();
+        let config = load(temp_dir.path()).unwrap();
+        assert!(config.groups.is_empty());
+    }
+
+    #[test]
+    fn should_load_from_file() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join(CONFIG_FILE), "[groups]\nweb = [\"frontend\"]").unwrap();
+
+        let config = load(temp_dir.path()).unwrap();
+        assert_eq!(vec!["frontend"], config.groups["web"]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::Write;
+use std::ops::AddAssign;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::{git, ERROR_WRITER};
+
+/// Changes of a single file, none for binary files
+#[derive(Debug, PartialEq)]
+pub struct FileStat {
+    pub file: String,
+    pub insertions: Option<usize>,
+    pub deletions: Option<usize>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct DiffStat {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl DiffStat {
+    pub fn of(files: &[FileStat]) -> DiffStat {
+        DiffStat {
+            files: files.len(),
+            insertions: files.iter().filter_map(|f| f.insertions).sum(),
+            deletions: files.iter().filter_map(|f| f.deletions).sum(),
+        }
+    }
+}
+
+impl AddAssign for DiffStat {
+    fn add_assign(&mut self, other: DiffStat) {
+        self.files += other.files;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+    }
+}
+
+/// Changed files of the repository at `path`, with `staged` the ones in the index
+pub fn numstat(path: &Path, staged: bool) -> Result<Vec<FileStat>, git::GitError> {
+    Ok(parse_numstat(&git::diff(path, staged, &["--numstat", "-z"])?))
+}
+
+/// Patch of the repository at `path` with file paths prefixed by `prefix`,
+/// e.g. the path of the repository relative to DEV_DIR
+pub fn patch(path: &Path, staged: bool, prefix: &str) -> Result<String, git::GitError> {
+    git::diff(
+        path,
+        staged,
+        &[&format!("--src-prefix=a/{}/", prefix), &format!("--dst-prefix=b/{}/", prefix)],
+    )
+}
+
+/// With `-z`, every file is `<insertions>\t<deletions>\t<path>\0`, renamed files have
+/// an empty path followed by `<from>\0<to>\0`. Binary files have `-` instead of numbers
+fn parse_numstat(out: &str) -> Vec<FileStat> {
+    let mut stats = Vec::new();
+    let mut fields = out.split('\0');
+    while let Some(entry) = fields.next() {
+        let mut parts = entry.splitn(3, '\t');
+        let (Some(insertions), Some(deletions), Some(file)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let file = if file.is_empty() {
+            let from = fields.next().unwrap_or_default();
+            let to = fields.next().unwrap_or_default();
+            format!("{} => {}", from, to)
+        } else {
+            file.to_string()
+        };
+        stats.push(FileStat {
+            file,
+            insertions: insertions.parse().ok(),
+            deletions: deletions.parse().ok(),
+        });
+    }
+    stats
+}
+
+/// Prints changed lines per file with file paths prefixed by `prefix`
+pub fn print_stat(prefix: &str, files: &[FileStat], mut writer: impl Write) {
+    for file in files {
+        let changes = match (file.insertions, file.deletions) {
+            (Some(insertions), Some(deletions)) => format!(
+                "{} {}",
+                format!("+{}", insertions).green(),
+                format!("-{}", deletions).red()
+            ),
+            _ => "binary".to_string(),
+        };
+        writeln!(writer, " {}/{} | {}", prefix.yellow(), file.file, changes).expect(ERROR_WRITER);
+    }
+}
+
+/// Prints files changed, insertions and deletions per repository followed by totals
+pub fn print_summary(stats: &[(String, DiffStat)], mut writer: impl Write) {
+    let width = stats.iter().map(|(repo, _)| repo.len()).max().unwrap_or_default().max(5);
+    let mut total = DiffStat::default();
+    for (repo, stat) in stats {
+        let repo = format!("{:width$}", repo, width = width);
+        writeln!(writer, "{} {}", repo.yellow(), describe(stat)).expect(ERROR_WRITER);
+        total += *stat;
+    }
+    writeln!(writer, "{:width$} {}", "Total", describe(&total), width = width)
+        .expect(ERROR_WRITER);
+}
+
+fn describe(stat: &DiffStat) -> String {
+    format!(
+        "{} file(s) changed, {} insertion(s), {} deletion(s)",
+        stat.files,
+        format!("+{}", stat.insertions).green(),
+        format!("-{}", stat.deletions).red()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    #[test]
+    fn should_parse_numstat() {
+        let out = "3\t1\tsrc/main.rs\0-\t-\tlogo.png\x000\t0\t\0old name.txt\0new name.txt\0";
+        assert_eq!(
+            vec![
+                FileStat { file: "src/main.rs".to_string(), insertions: Some(3), deletions: Some(1) },
+                FileStat { file: "logo.png".to_string(), insertions: None, deletions: None },
+                FileStat {
+                    file: "old name.txt => new name.txt".to_string(),
+                    insertions: Some(0),
+                    deletions: Some(0),
+                },
+            ],
+            parse_numstat(out)
+        );
+        assert_eq!(
+            DiffStat { files: 3, insertions: 3, deletions: 1 },
+            DiffStat::of(&parse_numstat(out))
+        );
+    }
+
+    #[test]
+    fn should_diff_working_tree_and_index() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        fs::write(path.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(path.join("b.txt"), "one\n").unwrap();
+        git(path, &["add", "."]);
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "-m", "init"]);
+
+        fs::write(path.join("a.txt"), "one\n2\nthree\n").unwrap();
+        fs::write(path.join("b.txt"), "").unwrap();
+        git(path, &["add", "b.txt"]);
+
+        let unstaged = numstat(path, false).unwrap();
+        assert_eq!(DiffStat { files: 1, insertions: 2, deletions: 1 }, DiffStat::of(&unstaged));
+        let staged = numstat(path, true).unwrap();
+        assert_eq!(DiffStat { files: 1, insertions: 0, deletions: 1 }, DiffStat::of(&staged));
+
+        let patch = patch(path, true, "clients/api").unwrap();
+        assert!(patch.contains("--- a/clients/api/b.txt"), "{}", patch);
+    }
+}
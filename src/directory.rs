@@ -1,10 +1,11 @@
-use std::path::{Path, PathBuf};
-use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::fs::{self, DirEntry};
 use anyhow::Context;
 
 pub const GIT_DIR: &str = ".git";
 pub const NAME_UNAVAILABLE: &str = "Name_Unavailable";
 
+#[derive(Debug)]
 pub struct Directory {
     pub name: String,
     pub path: PathBuf,
@@ -42,6 +43,51 @@ pub fn get_name(path: &Path) -> String {
         .to_string()
 }
 
+/// Whether `path` is relative and can't lead out of the directory it's joined to,
+/// i.e. it's not empty and has no root, `.` or `..` components
+pub fn is_relative_inside(path: &Path) -> bool {
+    let mut components = path.components().peekable();
+    components.peek().is_some() && components.all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Total size in bytes of the files under `path`. Symbolic links are not followed
+/// and entries which can't be read are left out
+pub fn dir_size(path: &Path) -> u64 {
+    entries_size(path, |_| true)
+}
+
+/// Size of the files of the repository at `path` without its `.git` directory
+pub fn work_tree_size(path: &Path) -> u64 {
+    entries_size(path, |entry| entry.file_name() != GIT_DIR)
+}
+
+fn entries_size(path: &Path, keep: impl Fn(&DirEntry) -> bool) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(keep)
+        .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?)))
+        .map(|(path, metadata)| if metadata.is_dir() { dir_size(&path) } else { metadata.len() })
+        .sum()
+}
+
+/// Human readable `bytes`, e.g. `512 B` or `1.5 MiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::{tempdir, tempdir_in};
@@ -57,6 +103,14 @@ mod tests {
         assert_eq!(source, result.path);
     }
 
+    #[test]
+    fn should_check_relative_paths_inside() {
+        assert!(is_relative_inside(Path::new("clients/api")));
+        assert!(!is_relative_inside(Path::new("")));
+        assert!(!is_relative_inside(Path::new("/clients/api")));
+        assert!(!is_relative_inside(Path::new("clients/../../api")));
+    }
+
     #[test]
     fn test_contains_git() {
         let dirs = vec![Directory {
@@ -84,14 +138,14 @@ mod tests {
     #[test]
     fn test_read_dirs() {
         let temp_dir = tempdir().unwrap();
-        let temp_sub_dir = tempdir_in(&temp_dir.path()).unwrap();
+        let temp_sub_dir = tempdir_in(temp_dir.path()).unwrap();
 
         let result = read_dirs(temp_dir.path()).unwrap();
 
         assert!(result.len().eq(&1));
         assert_eq!(
             temp_sub_dir.path().file_name().unwrap().to_str().unwrap(),
-            result.into_iter().nth(0).unwrap().name
+            result.into_iter().next().unwrap().name
         )
     }
 
@@ -108,4 +162,28 @@ mod tests {
         let result = read_dirs(Path::new("/not/existing")).unwrap();
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn should_sum_file_sizes() {
+        let temp_dir = tempdir().unwrap();
+        let sub_dir = temp_dir.path().join("sub");
+        fs::create_dir(&sub_dir).unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "12345").unwrap();
+        fs::write(sub_dir.join("b.txt"), "123").unwrap();
+
+        assert_eq!(8, dir_size(temp_dir.path()));
+        fs::create_dir(temp_dir.path().join(GIT_DIR)).unwrap();
+        fs::write(temp_dir.path().join(GIT_DIR).join("HEAD"), "ref").unwrap();
+        assert_eq!(11, dir_size(temp_dir.path()));
+        assert_eq!(8, work_tree_size(temp_dir.path()));
+        assert_eq!(0, dir_size(Path::new("/not/existing")));
+    }
+
+    #[test]
+    fn should_format_size() {
+        assert_eq!("512 B", format_size(512));
+        assert_eq!("1.5 KiB", format_size(1536));
+        assert_eq!("2.0 MiB", format_size(2 * 1024 * 1024));
+        assert_eq!("3.0 TiB", format_size(3 * 1024 * 1024 * 1024 * 1024));
+    }
 }
@@ -0,0 +1,399 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::commands::{selected_projects, Target};
+use crate::config::{self, Config};
+use crate::directory::GIT_DIR;
+use crate::git::{self, HeadState};
+use crate::project::{scan, Project};
+use crate::selector::wildcard_match;
+use crate::ERROR_WRITER;
+
+const ORIGIN: &str = "origin";
+/// Oldest git supporting all commands used by `please`
+const MIN_GIT_VERSION: (u32, u32) = (2, 29);
+
+/// Problem found by `doctor` with a hint how to fix it
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub problem: String,
+    pub fix: String,
+}
+
+impl Finding {
+    fn new(problem: String, fix: String) -> Finding {
+        Finding { problem, fix }
+    }
+}
+
+/// Runs all checks of the workspace at `root` and the repositories selected by `target`,
+/// printing them to `writer`. Without `root`, the environment variable `var` pointing
+/// to the workspace is not defined. Returns the number of problems found
+pub fn diagnose(
+    root: Option<&Path>,
+    var: &str,
+    target: &Target,
+    fsck: bool,
+    mut writer: impl Write,
+) -> Result<usize> {
+    if let Some(root) = root {
+        writeln!(writer, "Workspace found at {:?}", root).expect(ERROR_WRITER);
+    }
+    let version = git::version();
+    let (git_name, git_findings) = check_git(version.as_deref());
+    let mut problems = print_findings(&git_name, &git_findings, &mut writer);
+    problems += print_findings("DEV_DIR", &check_dev_dir(root, var), &mut writer);
+    let Some(root) = root.filter(|root| root.is_dir()) else {
+        return Ok(problems);
+    };
+    problems += print_findings("symlinks", &check_symlinks(root), &mut writer);
+    let config = match config::load(root) {
+        Ok(config) => {
+            print_findings(config::CONFIG_FILE, &[], &mut writer);
+            config
+        }
+        Err(e) => {
+            let finding = Finding::new(format!("{:#}", e), "fix or remove the file".to_string());
+            problems += print_findings(config::CONFIG_FILE, &[finding], &mut writer);
+            Config::default()
+        }
+    };
+
+    let projects = match scan(root) {
+        Ok(projects) => projects,
+        Err(e) => {
+            let finding = Finding::new(
+                format!("{:#}", e),
+                "make sure all directories in DEV_DIR are readable".to_string(),
+            );
+            return Ok(problems + print_findings("scan", &[finding], &mut writer));
+        }
+    };
+    problems += print_findings("projects", &check_duplicates(&projects, root), &mut writer);
+    if version.is_none() {
+        writeln!(writer, "Skipping checks of repositories, they need git").expect(ERROR_WRITER);
+        return Ok(problems);
+    }
+
+    let selected = selected_projects(root, target, &config)?;
+    Ok(problems + check_repositories(&selected, root, &config, fsck, writer))
+}
+
+/// Checks that git can be executed and is recent enough, given the output of
+/// `git --version` if any. Returns the version with findings
+pub fn check_git(version: Option<&str>) -> (String, Vec<Finding>) {
+    let Some(version) = version else {
+        let finding = Finding::new(
+            "git executable not found".to_string(),
+            "install git and make sure it is on PATH".to_string(),
+        );
+        return ("git".to_string(), vec![finding]);
+    };
+
+    let mut findings = Vec::new();
+    match parse_version(version) {
+        Some(parsed) if parsed < MIN_GIT_VERSION => findings.push(Finding::new(
+            format!("{} is older than {}.{}", version, MIN_GIT_VERSION.0, MIN_GIT_VERSION.1),
+            "upgrade git".to_string(),
+        )),
+        Some(_) => {}
+        None => findings.push(Finding::new(
+            format!("unrecognized version '{}'", version),
+            "check that 'git --version' runs git".to_string(),
+        )),
+    }
+    (version.to_string(), findings)
+}
+
+/// (major, minor) of `git version 2.43.0` or `git version 2.39.2.windows.1`
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut numbers = version.strip_prefix("git version ")?.split('.');
+    Some((numbers.next()?.parse().ok()?, numbers.next()?.parse().ok()?))
+}
+
+/// Checks that the workspace `root` resolved from the environment variable `var`
+/// or `--path` is a directory
+pub fn check_dev_dir(root: Option<&Path>, var: &str) -> Vec<Finding> {
+    let Some(root) = root else {
+        return vec![Finding::new(
+            format!("{} is not defined", var),
+            format!("export {}=<dir> or pass --path", var),
+        )];
+    };
+    let fix = format!("point {} to the directory with your projects or use --path", var);
+    match fs::metadata(root) {
+        Ok(metadata) if metadata.is_dir() => Vec::new(),
+        Ok(_) => vec![Finding::new(format!("{:?} is not a directory", root), fix)],
+        Err(e) => vec![Finding::new(format!("{:?} is not accessible: {}", root, e), fix)],
+    }
+}
+
+/// Symlinks pointing to missing targets in the directories `scan` walks through,
+/// which are silently skipped by it
+pub fn check_symlinks(root: &Path) -> Vec<Finding> {
+    let mut broken = Vec::new();
+    find_broken_symlinks(root, &mut broken);
+    broken
+        .into_iter()
+        .map(|link| {
+            let target = fs::read_link(&link).unwrap_or_default();
+            Finding::new(
+                format!("{:?} points to missing {:?}", link, target),
+                format!("remove it with 'rm {}' or restore the target", link.display()),
+            )
+        })
+        .collect()
+}
+
+fn find_broken_symlinks(dir: &Path, broken: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_symlink() {
+            if fs::metadata(&path).is_err() {
+                broken.push(path);
+            }
+        } else if file_type.is_dir() && !path.join(GIT_DIR).exists() {
+            find_broken_symlinks(&path, broken);
+        }
+    }
+}
+
+/// Projects sharing the same name, which can be selected only by their qualified names
+pub fn check_duplicates(projects: &[Project], root: &Path) -> Vec<Finding> {
+    let mut by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for project in projects {
+        by_name
+            .entry(project.name.to_lowercase())
+            .or_default()
+            .push(project.qualified_name(root));
+    }
+    by_name
+        .into_iter()
+        .filter(|(_, qualified)| qualified.len() > 1)
+        .map(|(name, qualified)| {
+            Finding::new(
+                format!("project name '{}' is used by {}", name, qualified.join(", ")),
+                format!("select them by qualified names, e.g. 'please status {}'", qualified[0]),
+            )
+        })
+        .collect()
+}
+
+/// Verifies that all objects reachable from refs of the repository at `path` are present
+pub fn check_fsck(path: &Path) -> Option<Finding> {
+    let output = git::query_cmd(&["fsck", "--connectivity-only", "--no-progress"], path);
+    if output.status.success() {
+        return None;
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let detail = stderr.lines().next().unwrap_or("git fsck failed").to_string();
+    Some(Finding::new(
+        format!("repository is corrupted: {}", detail),
+        "inspect it with 'git fsck --full', or clone it again".to_string(),
+    ))
+}
+
+/// Checks that the repository at `path` has `origin` with a URL matching one of
+/// `url_patterns` (any URL when empty) and that the current branch tracks an existing upstream
+pub fn check_remotes(path: &Path, url_patterns: &[String]) -> Result<Vec<Finding>, git::GitError> {
+    let mut findings = Vec::new();
+    let remotes = git::remotes(path)?;
+
+    if !remotes.iter().any(|r| r == ORIGIN) {
+        let fix = match remotes.first() {
+            Some(other) => format!("git remote rename {} {}", other, ORIGIN),
+            None => format!("git remote add {} <url>", ORIGIN),
+        };
+        findings.push(Finding::new(format!("no '{}' remote", ORIGIN), fix));
+    } else if !url_patterns.is_empty() {
+        let url = git::remote_url(path, ORIGIN)?;
+        if !url_patterns.iter().any(|pattern| wildcard_match(pattern, &url)) {
+            findings.push(Finding::new(
+                format!("{} URL {} does not match {}", ORIGIN, url, url_patterns.join(", ")),
+                format!("git remote set-url {} <url>", ORIGIN),
+            ));
+        }
+    }
+
+    if let HeadState::Branch(name) = git::head_state(path)? {
+        let branches = git::branches(path)?;
+        if let Some(branch) = branches.iter().find(|b| b.name == name) {
+            match &branch.upstream {
+                None => findings.push(Finding::new(
+                    format!("{} has no upstream", name),
+                    format!("git push --set-upstream {} {}", ORIGIN, name),
+                )),
+                Some(upstream) if branch.gone => findings.push(Finding::new(
+                    format!("upstream {} of {} is gone", upstream, name),
+                    format!("git branch --unset-upstream {}", name),
+                )),
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(findings)
+}
+
+/// Checks remotes of all repositories of the `projects` and with `fsck` their integrity,
+/// returns the number of problems found
+pub fn check_repositories(
+    projects: &[Project],
+    root: &Path,
+    config: &Config,
+    fsck: bool,
+    mut writer: impl Write,
+) -> usize {
+    let mut problems = 0;
+    for project in projects {
+        crate::commands::print_project(project, &mut writer);
+        let url_patterns = config.remote_urls(project, root);
+        for repo in project.repos.iter().flatten() {
+            let mut findings = match check_remotes(&repo.path, &url_patterns) {
+                Ok(findings) => findings,
+                Err(e) => {
+                    let fix = "inspect it with 'git status'".to_string();
+                    vec![Finding::new(e.to_string(), fix)]
+                }
+            };
+            if fsck {
+                findings.extend(check_fsck(&repo.path));
+            }
+            problems += print_findings(&repo.name, &findings, &mut writer);
+        }
+    }
+    problems
+}
+
+/// Prints `ok` or every finding of the check `name` with its fix, returns the number of findings
+pub fn print_findings(name: &str, findings: &[Finding], mut writer: impl Write) -> usize {
+    if findings.is_empty() {
+        writeln!(writer, "{} {}: ok", "=>".bright_green(), name.yellow()).expect(ERROR_WRITER);
+    }
+    for finding in findings {
+        writeln!(
+            writer,
+            "{} {}: {} {}",
+            "=>".red(),
+            name.yellow(),
+            "Problem".red(),
+            finding.problem
+        )
+        .expect(ERROR_WRITER);
+        writeln!(writer, "   fix: {}", finding.fix.bright_green()).expect(ERROR_WRITER);
+    }
+    findings.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn problems(path: &Path, url_patterns: &[String]) -> Vec<String> {
+        check_remotes(path, url_patterns).unwrap().into_iter().map(|f| f.problem).collect()
+    }
+
+    #[test]
+    fn should_parse_git_version() {
+        assert_eq!(Some((2, 43)), parse_version("git version 2.43.0"));
+        assert_eq!(Some((2, 39)), parse_version("git version 2.39.2.windows.1"));
+        assert_eq!(None, parse_version("hub version 2.14.2"));
+        assert!(check_git(git::version().as_deref()).1.is_empty());
+        assert_eq!(1, check_git(Some("git version 2.20.1")).1.len());
+        assert_eq!("git executable not found", check_git(None).1[0].problem);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn should_report_workspace_problems() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for repo in ["clients/api", "web/api", "web/portal"] {
+            fs::create_dir_all(root.join(repo).join(GIT_DIR)).unwrap();
+        }
+        std::os::unix::fs::symlink(root.join("missing"), root.join("clients/link")).unwrap();
+
+        assert!(check_dev_dir(Some(root), "DEV_DIR").is_empty());
+        assert_eq!(1, check_dev_dir(Some(&root.join("missing")), "DEV_DIR").len());
+        assert_eq!("export WORK=<dir> or pass --path", check_dev_dir(None, "WORK")[0].fix);
+
+        let symlinks = check_symlinks(root);
+        assert_eq!(1, symlinks.len());
+        assert!(symlinks[0].problem.contains("clients/link"), "{}", symlinks[0].problem);
+
+        let projects = vec![
+            Project { name: "api".to_string(), path: root.join("clients/api"), repos: None },
+            Project { name: "api".to_string(), path: root.join("web/api"), repos: None },
+            Project { name: "portal".to_string(), path: root.join("web/portal"), repos: None },
+        ];
+        let duplicates = check_duplicates(&projects, root);
+        assert_eq!(1, duplicates.len());
+        assert_eq!("project name 'api' is used by clients/api, web/api", duplicates[0].problem);
+    }
+
+    #[test]
+    fn should_report_corrupted_repository() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        fs::write(path.join("file.txt"), "content").unwrap();
+        git(path, &["add", "."]);
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "-m", "init"]);
+        assert_eq!(None, check_fsck(path));
+
+        let objects = path.join(GIT_DIR).join("objects");
+        let output = git::query_cmd(&["rev-parse", "HEAD^{tree}"], path);
+        let tree = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        fs::remove_file(objects.join(&tree[..2]).join(&tree[2..])).unwrap();
+        assert!(check_fsck(path).is_some());
+    }
+
+    #[test]
+    fn should_report_remote_problems() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "init"]);
+        git(path, &["remote", "add", "upstream", "git@github.com:other/api.git"]);
+
+        let findings = check_remotes(path, &[]).unwrap();
+        assert_eq!("no 'origin' remote", findings[0].problem);
+        assert_eq!("git remote rename upstream origin", findings[0].fix);
+        assert_eq!("main has no upstream", findings[1].problem);
+
+        git(path, &["remote", "rename", "upstream", "origin"]);
+        git(path, &["update-ref", "refs/remotes/origin/main", "HEAD"]);
+        git(path, &["branch", "-u", "origin/main"]);
+        assert!(problems(path, &[]).is_empty());
+
+        let patterns = vec!["git@github.com:my-org/*".to_string()];
+        assert_eq!(
+            vec!["origin URL git@github.com:other/api.git does not match git@github.com:my-org/*"],
+            problems(path, &patterns)
+        );
+
+        git(path, &["update-ref", "-d", "refs/remotes/origin/main"]);
+        assert_eq!(vec!["upstream origin/main of main is gone"], problems(path, &[]));
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::Path;
+
+use clap::Args;
+
+use crate::git;
+use crate::project::Project;
+
+/// Conditions on the state of a repository, checked before a command is executed in it.
+/// Ahead / behind are compared with the last fetched state of the upstream
+#[derive(Args, Debug, Default)]
+pub struct RepoFilter {
+    /// Only repositories with uncommitted or untracked changes
+    #[arg(long)]
+    pub dirty: bool,
+
+    /// Only repositories with commits not pushed to the upstream
+    #[arg(long)]
+    pub ahead: bool,
+
+    /// Only repositories with upstream commits not pulled yet
+    #[arg(long)]
+    pub behind: bool,
+
+    /// Only repositories with given branch checked out
+    #[arg(long, value_name = "BRANCH")]
+    pub on_branch: Option<String>,
+
+    /// Only repositories with other than the default branch checked out
+    #[arg(long)]
+    pub not_default_branch: bool,
+
+    /// Only repositories with a merge, rebase, cherry-pick, revert or bisect in progress
+    #[arg(long)]
+    pub in_progress: bool,
+}
+
+impl RepoFilter {
+    pub fn is_active(&self) -> bool {
+        self.dirty
+            || self.ahead
+            || self.behind
+            || self.on_branch.is_some()
+            || self.not_default_branch
+            || self.in_progress
+    }
+
+    /// Checks all conditions for the repository at `path`.
+    /// Repositories which state can't be read don't match
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.dirty && !git::is_dirty(path).unwrap_or(false) {
+            return false;
+        }
+
+        if self.in_progress && !git::head_state(path).is_ok_and(|s| s.in_progress()) {
+            return false;
+        }
+
+        if self.ahead || self.behind {
+            let (ahead, behind) = git::ahead_behind(path).ok().flatten().unwrap_or((0, 0));
+            if (self.ahead && ahead == 0) || (self.behind && behind == 0) {
+                return false;
+            }
+        }
+
+        if self.on_branch.is_some() || self.not_default_branch {
+            let current = match git::current_branch(path) {
+                Ok(current) => current,
+                Err(_) => return false,
+            };
+            if let Some(branch) = &self.on_branch {
+                if !branch.eq(&current) {
+                    return false;
+                }
+            }
+            if self.not_default_branch {
+                match git::default_branch(path) {
+                    Ok(Some(default)) if default.eq(&current) => return false,
+                    Err(_) => return false,
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Leaves only repositories matching the `filter`, dropping projects with no repositories left
+pub fn apply(projects: Vec<Project>, filter: &RepoFilter) -> Vec<Project> {
+    if !filter.is_active() {
+        return projects;
+    }
+
+    projects
+        .into_iter()
+        .filter_map(|project| {
+            let Project { name, path, repos } = project;
+            let repos: Vec<_> = repos?
+                .into_iter()
+                .filter(|repo| filter.matches(&repo.path))
+                .collect();
+            if repos.is_empty() {
+                None
+            } else {
+                Some(Project { name, path, repos: Some(repos) })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use tempfile::{tempdir, TempDir};
+
+    use crate::directory::Directory;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn init_repo(path: &Path) {
+        fs::create_dir_all(path).unwrap();
+        git(path, &["init", "-b", "main"]);
+        git(path, &["config", "user.name", "Tester"]);
+        git(path, &["config", "user.email", "tester@example.com"]);
+        commit(path, "README.md");
+    }
+
+    fn commit(path: &Path, file: &str) {
+        fs::write(path.join(file), file).unwrap();
+        git(path, &["add", "."]);
+        git(path, &["commit", "-m", file]);
+    }
+
+    /// Upstream repository `origin` with a clone `local`
+    fn init_cloned() -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let origin = temp_dir.path().join("origin");
+        let local = temp_dir.path().join("local");
+        init_repo(&origin);
+        git(temp_dir.path(), &["clone", "origin", "local"]);
+        git(&local, &["config", "user.name", "Tester"]);
+        git(&local, &["config", "user.email", "tester@example.com"]);
+        (temp_dir, origin, local)
+    }
+
+    #[test]
+    fn inactive_filter_matches_everything() {
+        let filter = RepoFilter::default();
+        assert!(!filter.is_active());
+
+        let projects = vec![Project {
+            name: "Project".to_string(),
+            path: PathBuf::from("/not/existing"),
+            repos: Some(vec![Directory::from(PathBuf::from("/not/existing/repo"))]),
+        }];
+        assert_eq!(1, apply(projects, &filter).len());
+    }
+
+    #[test]
+    fn should_match_dirty() {
+        let temp_dir = tempdir().unwrap();
+        init_repo(temp_dir.path());
+        let filter = RepoFilter { dirty: true, ..Default::default() };
+
+        assert!(!filter.matches(temp_dir.path()));
+        fs::write(temp_dir.path().join("new.txt"), "new").unwrap();
+        assert!(filter.matches(temp_dir.path()));
+    }
+
+    #[test]
+    fn should_match_ahead_and_behind() {
+        let (_temp_dir, origin, local) = init_cloned();
+        let ahead = RepoFilter { ahead: true, ..Default::default() };
+        let behind = RepoFilter { behind: true, ..Default::default() };
+
+        assert!(!ahead.matches(&local));
+        assert!(!behind.matches(&local));
+
+        commit(&local, "local.txt");
+        assert!(ahead.matches(&local));
+        assert!(!behind.matches(&local));
+
+        commit(&origin, "origin.txt");
+        git(&local, &["fetch"]);
+        assert!(behind.matches(&local));
+    }
+
+    #[test]
+    fn should_not_match_ahead_without_upstream() {
+        let temp_dir = tempdir().unwrap();
+        init_repo(temp_dir.path());
+        let filter = RepoFilter { ahead: true, ..Default::default() };
+        assert!(!filter.matches(temp_dir.path()));
+    }
+
+    #[test]
+    fn should_match_branches() {
+        let (_temp_dir, _origin, local) = init_cloned();
+        let on_main = RepoFilter { on_branch: Some("main".to_string()), ..Default::default() };
+        let not_default = RepoFilter { not_default_branch: true, ..Default::default() };
+
+        assert!(on_main.matches(&local));
+        assert!(!not_default.matches(&local));
+
+        git(&local, &["checkout", "-b", "feature"]);
+        assert!(!on_main.matches(&local));
+        assert!(not_default.matches(&local));
+    }
+
+    #[test]
+    fn should_match_operation_in_progress() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        init_repo(path);
+        let filter = RepoFilter { in_progress: true, ..Default::default() };
+        assert!(!filter.matches(path));
+
+        git(path, &["bisect", "start"]);
+        assert!(filter.matches(path));
+    }
+
+    #[test]
+    fn should_drop_projects_without_matching_repos() {
+        let temp_dir = tempdir().unwrap();
+        let clean = temp_dir.path().join("clean");
+        let dirty = temp_dir.path().join("dirty");
+        init_repo(&clean);
+        init_repo(&dirty);
+        fs::write(dirty.join("new.txt"), "new").unwrap();
+
+        let projects = vec![
+            Project {
+                name: "first".to_string(),
+                path: temp_dir.path().to_path_buf(),
+                repos: Some(vec![Directory::from(clean.clone()), Directory::from(dirty)]),
+            },
+            Project {
+                name: "second".to_string(),
+                path: temp_dir.path().to_path_buf(),
+                repos: Some(vec![Directory::from(clean)]),
+            },
+        ];
+
+        let result = apply(projects, &RepoFilter { dirty: true, ..Default::default() });
+        assert_eq!(1, result.len());
+        assert_eq!("dirty", result[0].repos.as_ref().unwrap()[0].name);
+    }
+}
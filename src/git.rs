@@ -1,7 +1,7 @@
 use std::env::consts::OS;
 use std::fmt::{Display, Formatter};
-use std::path::Path;
-use std::process::{Child, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Output, Stdio};
 
 use anyhow::Result;
 use colored::Colorize;
@@ -13,6 +13,36 @@ pub const GIT_STATUS: &str = "status";
 pub const GIT_CHECKOUT: &str = "checkout";
 pub const GIT_BRANCH: &str = "branch";
 
+/// Branches considered as a target for `clean` and as a default when `origin/HEAD` is not set,
+/// in order of precedence
+pub const DEFAULT_BRANCHES: [&str; 3] = ["develop", "main", "master"];
+const ORIGIN_HEAD: &str = "refs/remotes/origin/HEAD";
+const LOCAL_REFS: &str = "refs/heads/";
+const REMOTE_REFS: &str = "refs/remotes/";
+const BRANCH_FORMAT: &str = "--format=%(HEAD)%1f%(refname)%1f%(upstream:short)%1f\
+    %(upstream:track,nobracket)%1f%(objectname:short)%1f%(committerdate:unix)%1f\
+    %(committerdate:short)%1f%(authorname)";
+
+/// Branch with its upstream tracking state and last commit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branch {
+    pub name: String,
+    /// Checked out in the repository
+    pub head: bool,
+    pub upstream: Option<String>,
+    /// Upstream is configured, but does not exist anymore
+    pub gone: bool,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Abbreviated hash of the last commit
+    pub commit: String,
+    /// Committer date of the last commit, seconds since the Unix epoch
+    pub timestamp: i64,
+    /// Committer date of the last commit as `YYYY-MM-DD`
+    pub date: String,
+    pub author: String,
+}
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum GitError {
@@ -25,7 +55,21 @@ pub enum GitError {
     CurrentBranchCode(i32),
     CurrentBranch,
     ReadBranchesCode(i32),
-    ReadBranches
+    ReadBranches,
+    ReadStateCode(String, i32),
+    ReadState(String),
+    CloneCode(String, i32),
+    Clone(String),
+    CommitCode(i32),
+    Commit,
+    PushCode(i32),
+    Push,
+    StashCode(String, i32),
+    Stash(String),
+    RemoteCode(String, i32),
+    Remote(String),
+    MaintenanceCode(String, i32),
+    Maintenance(String),
 }
 
 impl Display for GitError {
@@ -51,13 +95,95 @@ impl Display for GitError {
             ReadBranchesCode(code) =>
                 write!(f, "Unable to read branches. Code[{}]", code),
             ReadBranches =>
-                write!(f, "Unable to read branches")
+                write!(f, "Unable to read branches"),
+            ReadStateCode(what, code) =>
+                write!(f, "Unable to read {}. Code[{}]", what, code),
+            ReadState(what) =>
+                write!(f, "Unable to read {}", what),
+            CloneCode(url, code) =>
+                write!(f, "Cloning {} failed. Code[{}]", url, code),
+            Clone(url) =>
+                write!(f, "Cloning {} failed with an unexpected error", url),
+            CommitCode(code) =>
+                write!(f, "Git commit errored. Code[{}]", code),
+            Commit =>
+                write!(f, "Git commit failed with an unexpected error"),
+            PushCode(code) =>
+                write!(f, "Git push errored. Code[{}]", code),
+            Push =>
+                write!(f, "Git push failed with an unexpected error"),
+            StashCode(op, code) =>
+                write!(f, "Git stash {} errored. Code[{}]", op, code),
+            Stash(op) =>
+                write!(f, "Git stash {} failed with an unexpected error", op),
+            RemoteCode(op, code) =>
+                write!(f, "Git remote {} errored. Code[{}]", op, code),
+            Remote(op) =>
+                write!(f, "Git remote {} failed with an unexpected error", op),
+            MaintenanceCode(op, code) =>
+                write!(f, "Git {} errored. Code[{}]", op, code),
+            Maintenance(op) =>
+                write!(f, "Git {} failed with an unexpected error", op),
         }
     }
 }
 
 impl std::error::Error for GitError {}
 
+/// What HEAD of a repository points to, including operations left in progress
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadState {
+    Branch(String),
+    /// Abbreviated hash of the checked out commit
+    Detached(String),
+    /// Branch without any commit yet
+    Unborn(String),
+    Rebasing,
+    Merging,
+    CherryPicking,
+    Reverting,
+    Bisecting,
+}
+
+impl HeadState {
+    pub fn is_branch(&self) -> bool {
+        matches!(self, HeadState::Branch(_))
+    }
+
+    /// Command abandoning the operation in progress, none when there is no such operation
+    pub fn abort_command(&self) -> Option<&'static str> {
+        use HeadState::*;
+        match self {
+            Rebasing => Some("git rebase --abort"),
+            Merging => Some("git merge --abort"),
+            CherryPicking => Some("git cherry-pick --abort"),
+            Reverting => Some("git revert --abort"),
+            Bisecting => Some("git bisect reset"),
+            Branch(_) | Detached(_) | Unborn(_) => None,
+        }
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.abort_command().is_some()
+    }
+}
+
+impl Display for HeadState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        use HeadState::*;
+        match self {
+            Branch(branch) => write!(f, "on {}", branch),
+            Detached(commit) => write!(f, "HEAD detached at {}", commit),
+            Unborn(branch) => write!(f, "no commits yet on {}", branch),
+            Rebasing => write!(f, "rebase in progress"),
+            Merging => write!(f, "merge in progress"),
+            CherryPicking => write!(f, "cherry-pick in progress"),
+            Reverting => write!(f, "revert in progress"),
+            Bisecting => write!(f, "bisect in progress"),
+        }
+    }
+}
+
 pub fn checkout(target: String) -> Result<(), GitError> {
     let cmd_output = two_args_cmd(GIT_CHECKOUT, &target).wait_with_output().unwrap();
 
@@ -68,6 +194,132 @@ pub fn checkout(target: String) -> Result<(), GitError> {
     }
 }
 
+/// Quietly checks out `target` in the repository at `path`, even when a file has the same name
+pub fn checkout_in(path: &Path, target: &str) -> Result<(), GitError> {
+    let cmd_output = cwd_args_cmd(&[GIT_CHECKOUT, "-q", target, "--"], path)
+        .wait_with_output().unwrap();
+
+    match cmd_output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::CheckoutCode(target.to_string(), code)),
+        None => Err(GitError::Checkout(target.to_string()))
+    }
+}
+
+/// Creates branch `name` from `start` and checks it out in the repository at `path`.
+/// The branch never tracks `start`, even when it is a remote-tracking branch
+pub fn create_branch(path: &Path, name: &str, start: &str) -> Result<(), GitError> {
+    let cmd_output = cwd_args_cmd(&[GIT_CHECKOUT, "-q", "--no-track", "-b", name, start], path)
+        .wait_with_output().unwrap();
+
+    match cmd_output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::CheckoutCode(name.to_string(), code)),
+        None => Err(GitError::Checkout(name.to_string()))
+    }
+}
+
+/// Checks whether the index of the repository at `path` has changes staged for a commit
+pub fn has_staged(path: &Path) -> Result<bool, GitError> {
+    let output = query_cmd(&["diff", "--cached", "--quiet"], path);
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        Some(code) => Err(GitError::ReadStateCode("staged changes".to_string(), code)),
+        None => Err(GitError::ReadState("staged changes".to_string())),
+    }
+}
+
+/// Commits staged changes of the repository at `path` with given `message`
+pub fn commit(path: &Path, message: &str) -> Result<(), GitError> {
+    let cmd_output = cwd_args_cmd(&["commit", "-q", "-m", message], path)
+        .wait_with_output().unwrap();
+
+    match cmd_output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::CommitCode(code)),
+        None => Err(GitError::Commit)
+    }
+}
+
+/// Pushes the current branch of the repository at `path` to its upstream,
+/// or to the `branch` on the `remote` set as the new upstream
+pub fn push(path: &Path, set_upstream: Option<(&str, &str)>) -> Result<(), GitError> {
+    let args = match set_upstream {
+        Some((remote, branch)) => vec!["push", "-q", "-u", remote, branch],
+        None => vec!["push", "-q"],
+    };
+    let cmd_output = cwd_args_cmd(&args, path).wait_with_output().unwrap();
+
+    match cmd_output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::PushCode(code)),
+        None => Err(GitError::Push)
+    }
+}
+
+/// Stashes all changes, including untracked files, of the repository at `path`
+pub fn stash_push(path: &Path, message: &str) -> Result<(), GitError> {
+    let output = query_cmd(&["stash", "push", "--include-untracked", "-m", message], path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::StashCode("push".to_string(), code)),
+        None => Err(GitError::Stash("push".to_string())),
+    }
+}
+
+/// Applies and drops the stash `reference` (e.g. `stash@{0}`) in the repository at `path`
+pub fn stash_pop(path: &Path, reference: &str) -> Result<(), GitError> {
+    let output = query_cmd(&["stash", "pop", "-q", reference], path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::StashCode("pop".to_string(), code)),
+        None => Err(GitError::Stash("pop".to_string())),
+    }
+}
+
+/// Stashes of the repository at `path` as (reference, subject) pairs, the newest first
+pub fn stash_list(path: &Path) -> Result<Vec<(String, String)>, GitError> {
+    let out = read(&["stash", "list", "--format=%gd%x1f%gs"], path, "stashes")?;
+    Ok(out
+        .lines()
+        .filter_map(|l| l.split_once('\x1f'))
+        .map(|(reference, subject)| (reference.to_string(), subject.to_string()))
+        .collect())
+}
+
+/// Files with unresolved conflicts in the repository at `path`
+pub fn conflicted_files(path: &Path) -> Result<Vec<String>, GitError> {
+    let out = read(&["diff", "--name-only", "--diff-filter=U"], path, "conflicts")?;
+    Ok(out.lines().map(|l| l.to_string()).collect())
+}
+
+/// Full hash of the commit `revision` points to in the repository at `path`
+pub fn rev_parse(path: &Path, revision: &str) -> Result<String, GitError> {
+    read(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)], path, "commit")
+}
+
+/// Abbreviated hash of the commit HEAD points to
+pub fn head_commit(path: &Path) -> Result<String, GitError> {
+    read(&["rev-parse", "--short", "HEAD"], path, "HEAD commit")
+}
+
+/// Output of `git diff` with `args` in the repository at `path`, comparing the working tree
+/// with the index, or with `staged` the index with HEAD
+pub fn diff(path: &Path, staged: bool, args: &[&str]) -> Result<String, GitError> {
+    let mut diff_args = vec!["diff", "--no-color"];
+    if staged {
+        diff_args.push("--cached");
+    }
+    diff_args.extend(args);
+    read(&diff_args, path, "diff")
+}
+
+/// Checks whether `reference` (e.g. `refs/heads/main`) exists in the repository at `path`
+pub fn ref_exists(path: &Path, reference: &str) -> bool {
+    query_cmd(&["rev-parse", "--verify", "--quiet", reference], path).status.success()
+}
+
 pub fn pull() -> Result<(), GitError> {
     let cmd_output = one_arg_cmd(GIT_PULL).wait_with_output().unwrap();
 
@@ -106,26 +358,321 @@ pub fn get_curr_branch() -> Result<String, GitError> {
     }
 }
 
-pub fn get_branches() -> Result<Vec<String>, GitError> {
-    let cmd_output = one_arg_cmd(GIT_BRANCH).wait_with_output().unwrap();
+/// Local branches of the current directory, see [branches]
+pub fn get_branches() -> Result<Vec<Branch>, GitError> {
+    list_branches(Path::new("."), LOCAL_REFS)
+}
+
+/// Clones `url` into `target`, checking out `branch` if given
+pub fn clone(url: &str, branch: Option<&str>, target: &Path) -> Result<(), GitError> {
+    let mut cmd = std::process::Command::new(by_os());
+    cmd.arg("clone");
+    if let Some(branch) = branch {
+        cmd.arg("--branch").arg(branch);
+    }
+    let cmd_output = cmd.arg("--").arg(url).arg(target)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
     match cmd_output.status.code() {
-        Some(0) => {
-            let sanitized = String::from_utf8_lossy(&cmd_output.stdout)
-                .to_string()
-                .replace("*", "");
-            let branches = sanitized
-                .trim_end()
-                .split("\n")
-                .map(|s| s.trim_start())
-                .map(|s| s.to_string())
-                .collect::<Vec<String>>();
-            Ok(branches)
-        }
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::CloneCode(url.to_string(), code)),
+        None => Err(GitError::Clone(url.to_string()))
+    }
+}
+
+/// Checks whether the repository at `path` has any uncommitted or untracked changes
+pub fn is_dirty(path: &Path) -> Result<bool, GitError> {
+    let out = read(&["status", "--porcelain"], path, "working tree status")?;
+    Ok(!out.is_empty())
+}
+
+/// Current branch of the repository at `path`, empty when HEAD is detached
+pub fn current_branch(path: &Path) -> Result<String, GitError> {
+    read(&[GIT_BRANCH, "--show-current"], path, "current branch")
+}
+
+/// Absolute path of the `.git` directory, which differs from `path/.git` for worktrees
+pub fn git_dir(path: &Path) -> Result<PathBuf, GitError> {
+    read(&["rev-parse", "--absolute-git-dir"], path, "git directory").map(PathBuf::from)
+}
+
+/// State of HEAD of the repository at `path`. Operations in progress are recognized
+/// by the files git keeps in the `.git` directory until they are finished or aborted
+pub fn head_state(path: &Path) -> Result<HeadState, GitError> {
+    let git_dir = git_dir(path)?;
+    if git_dir.join("rebase-merge").is_dir() || git_dir.join("rebase-apply").is_dir() {
+        return Ok(HeadState::Rebasing);
+    }
+    if git_dir.join("MERGE_HEAD").is_file() {
+        return Ok(HeadState::Merging);
+    }
+    if git_dir.join("CHERRY_PICK_HEAD").is_file() {
+        return Ok(HeadState::CherryPicking);
+    }
+    if git_dir.join("REVERT_HEAD").is_file() {
+        return Ok(HeadState::Reverting);
+    }
+    if git_dir.join("BISECT_LOG").is_file() {
+        return Ok(HeadState::Bisecting);
+    }
+
+    let output = query_cmd(&["symbolic-ref", "-q", "--short", "HEAD"], path);
+    if output.status.success() {
+        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return if ref_exists(path, "HEAD") {
+            Ok(HeadState::Branch(branch))
+        } else {
+            Ok(HeadState::Unborn(branch))
+        };
+    }
+    Ok(HeadState::Detached(head_commit(path)?))
+}
+
+/// Names of local branches of the repository at `path`
+pub fn local_branches(path: &Path) -> Result<Vec<String>, GitError> {
+    Ok(branches(path)?.into_iter().map(|b| b.name).collect())
+}
+
+/// Local branches of the repository at `path`, sorted by name
+pub fn branches(path: &Path) -> Result<Vec<Branch>, GitError> {
+    list_branches(path, LOCAL_REFS)
+}
+
+/// Remote-tracking branches of the repository at `path` named `<remote>/<branch>`,
+/// without symbolic refs like `origin/HEAD`
+pub fn remote_branches(path: &Path) -> Result<Vec<Branch>, GitError> {
+    Ok(list_branches(path, REMOTE_REFS)?
+        .into_iter()
+        .filter(|b| !b.name.ends_with("/HEAD"))
+        .collect())
+}
+
+/// Reads branches with `for-each-ref`, which unlike `git branch` output has no markers
+/// for the current branch, detached HEAD or branches checked out in other worktrees
+fn list_branches(path: &Path, prefix: &str) -> Result<Vec<Branch>, GitError> {
+    let output = query_cmd(&["for-each-ref", BRANCH_FORMAT, prefix], path);
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| parse_branch(line, prefix))
+            .collect()),
         Some(code) => Err(GitError::ReadBranchesCode(code)),
-        None => Err(GitError::ReadBranches)
+        None => Err(GitError::ReadBranches),
     }
 }
 
+/// Deletes local `branch` of the repository at `path`. Unless `force` is set, git refuses
+/// to delete it when it is not merged into its upstream or HEAD
+pub fn delete_branch(path: &Path, branch: &str, force: bool) -> Result<(), GitError> {
+    let output = query_cmd(&["branch", if force { "-D" } else { "-d" }, branch], path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::DeleteCode(branch.to_string(), code)),
+        None => Err(GitError::Delete(branch.to_string())),
+    }
+}
+
+/// Deletes `branch` on the `remote` of the repository at `path`
+pub fn delete_remote_branch(path: &Path, remote: &str, branch: &str) -> Result<(), GitError> {
+    let output = query_cmd(&["push", "-q", remote, "--delete", branch], path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::DeleteCode(format!("{}/{}", remote, branch), code)),
+        None => Err(GitError::Delete(format!("{}/{}", remote, branch))),
+    }
+}
+
+/// Names of local branches merged into `target` in the repository at `path`
+pub fn merged_branches(path: &Path, target: &str) -> Result<Vec<String>, GitError> {
+    merged_refs(path, target, LOCAL_REFS)
+}
+
+/// Names of remote-tracking branches, e.g. `origin/feature`, merged into `target`
+/// in the repository at `path`
+pub fn merged_remote_branches(path: &Path, target: &str) -> Result<Vec<String>, GitError> {
+    merged_refs(path, target, REMOTE_REFS)
+}
+
+fn merged_refs(path: &Path, target: &str, prefix: &str) -> Result<Vec<String>, GitError> {
+    let merged = format!("--merged={}", target);
+    let out = read(
+        &["for-each-ref", "--format=%(refname)", &merged, prefix],
+        path,
+        "merged branches",
+    )?;
+    Ok(out.lines().filter_map(|l| l.strip_prefix(prefix)).map(|l| l.to_string()).collect())
+}
+
+/// Branch of a `for-each-ref` line, none when the line is malformed, as a branch with
+/// an unknown commit date must not look older than it is
+fn parse_branch(line: &str, prefix: &str) -> Option<Branch> {
+    let fields: Vec<&str> = line.split('\x1f').collect();
+    let [head, refname, upstream, track, commit, timestamp, date, author] = fields[..] else {
+        return None;
+    };
+
+    let mut branch = Branch {
+        name: refname.strip_prefix(prefix).unwrap_or(refname).to_string(),
+        head: head == "*",
+        upstream: Some(upstream.to_string()).filter(|u| !u.is_empty()),
+        gone: track == "gone",
+        ahead: 0,
+        behind: 0,
+        commit: commit.to_string(),
+        timestamp: timestamp.parse().ok()?,
+        date: date.to_string(),
+        author: author.to_string(),
+    };
+    // e.g. "ahead 1, behind 2"
+    for part in track.split(", ") {
+        match part.split_once(' ') {
+            Some(("ahead", count)) => branch.ahead = count.parse().unwrap_or_default(),
+            Some(("behind", count)) => branch.behind = count.parse().unwrap_or_default(),
+            _ => {}
+        }
+    }
+    Some(branch)
+}
+
+/// Names of remotes configured in the repository at `path`
+pub fn remotes(path: &Path) -> Result<Vec<String>, GitError> {
+    let out = read(&["remote"], path, "remotes")?;
+    Ok(out.lines().map(|l| l.to_string()).collect())
+}
+
+/// Fetch URL of the `remote` in the repository at `path`
+pub fn remote_url(path: &Path, remote: &str) -> Result<String, GitError> {
+    read(&["remote", "get-url", remote], path, "remote URL")
+}
+
+/// Adds `remote` with given `url` to the repository at `path`
+pub fn add_remote(path: &Path, remote: &str, url: &str) -> Result<(), GitError> {
+    edit_remote(&["add", remote, url], path)
+}
+
+/// Changes the URL of the `remote` in the repository at `path`
+pub fn set_remote_url(path: &Path, remote: &str, url: &str) -> Result<(), GitError> {
+    edit_remote(&["set-url", remote, url], path)
+}
+
+/// Renames the remote `old` to `new` together with its remote-tracking branches
+pub fn rename_remote(path: &Path, old: &str, new: &str) -> Result<(), GitError> {
+    edit_remote(&["rename", old, new], path)
+}
+
+fn edit_remote(args: &[&str], path: &Path) -> Result<(), GitError> {
+    let mut remote_args = vec!["remote"];
+    remote_args.extend(args);
+    let output = query_cmd(&remote_args, path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::RemoteCode(args[0].to_string(), code)),
+        None => Err(GitError::Remote(args[0].to_string())),
+    }
+}
+
+/// Optimizes the repository at `path` with the tasks of `git maintenance run`, `gc` by default.
+/// With `aggressive` runs `git gc --aggressive` and drops all unreachable objects instead
+pub fn maintenance(path: &Path, aggressive: bool) -> Result<(), GitError> {
+    if aggressive {
+        run_maintenance("gc", &["gc", "--quiet", "--aggressive", "--prune=now"], path)
+    } else {
+        run_maintenance("maintenance run", &["maintenance", "run", "--quiet"], path)
+    }
+}
+
+/// Deletes remote-tracking branches of `remote` which no longer exist on it
+pub fn prune_remote(path: &Path, remote: &str) -> Result<(), GitError> {
+    run_maintenance(&format!("remote prune {}", remote), &["remote", "prune", remote], path)
+}
+
+fn run_maintenance(op: &str, args: &[&str], path: &Path) -> Result<(), GitError> {
+    let output = query_cmd(args, path);
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(GitError::MaintenanceCode(op.to_string(), code)),
+        None => Err(GitError::Maintenance(op.to_string())),
+    }
+}
+
+/// Name of the branch on the remote the local `branch` is pushed to and pulled from,
+/// which differs from `branch` e.g. for `feature/x` tracking `origin/main`
+pub fn upstream_branch(path: &Path, branch: &str) -> Option<String> {
+    let merge = read(&["config", &format!("branch.{}.merge", branch)], path, "upstream").ok()?;
+    Some(merge.strip_prefix(LOCAL_REFS).unwrap_or(&merge).to_string())
+}
+
+/// Number of commits the current branch is (ahead, behind) its upstream,
+/// `None` when there is no upstream configured
+pub fn ahead_behind(path: &Path) -> Result<Option<(usize, usize)>, GitError> {
+    let output = query_cmd(
+        &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"],
+        path,
+    );
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let out = String::from_utf8_lossy(&output.stdout);
+    let mut counts = out.split_whitespace().map(|c| c.parse::<usize>());
+    match (counts.next(), counts.next()) {
+        (Some(Ok(ahead)), Some(Ok(behind))) => Ok(Some((ahead, behind))),
+        _ => Err(GitError::ReadState("ahead / behind counts".to_string())),
+    }
+}
+
+/// Branch pointed by `origin/HEAD`, otherwise the first existing branch of [DEFAULT_BRANCHES]
+pub fn default_branch(path: &Path) -> Result<Option<String>, GitError> {
+    let output = query_cmd(&["symbolic-ref", "--short", ORIGIN_HEAD], path);
+    if output.status.success() {
+        let remote_head = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if let Some((_, branch)) = remote_head.split_once('/') {
+            return Ok(Some(branch.to_string()));
+        }
+    }
+
+    let branches = local_branches(path)?;
+    Ok(DEFAULT_BRANCHES
+        .iter()
+        .find(|b| branches.iter().any(|branch| branch.eq(*b)))
+        .map(|b| b.to_string()))
+}
+
+fn read(args: &[&str], path: &Path, what: &str) -> Result<String, GitError> {
+    let output = query_cmd(args, path);
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string()),
+        Some(code) => Err(GitError::ReadStateCode(what.to_string(), code)),
+        None => Err(GitError::ReadState(what.to_string())),
+    }
+}
+
+/// Output of `git --version`, none when the git executable can't be run
+pub fn version() -> Option<String> {
+    let output = std::process::Command::new(by_os())
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Runs git with `args` in `path`, capturing both stdout and stderr
+pub fn query_cmd(args: &[&str], path: &Path) -> Output {
+    std::process::Command::new(by_os())
+        .args(args)
+        .current_dir(path)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
 fn one_arg_cmd(arg: &str) -> Child {
     std::process::Command::new(by_os())
         .arg(arg)
@@ -163,6 +710,17 @@ pub fn custom_cwd_cmd(arg: &str, path: &Path) -> Child {
         .unwrap()
 }
 
+/// Like [custom_cwd_cmd], but with any number of arguments
+pub fn cwd_args_cmd(args: &[&str], path: &Path) -> Child {
+    std::process::Command::new(by_os())
+        .args(args)
+        .current_dir(path)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
 fn by_os() -> &'static str {
     if OS.eq("windows") {
         GIT_EXEC_WINDOWS
@@ -174,6 +732,7 @@ fn by_os() -> &'static str {
 #[cfg(test)]
 mod tests {
     use std::env;
+    use std::fs;
     use std::process::Output;
 
     use tempfile::{tempdir, TempDir};
@@ -240,6 +799,133 @@ mod tests {
         validate_correct_branch(&check, "On branch main");
     }
 
+    #[test]
+    fn should_resolve_default_branch_without_origin() {
+        let temp_dir = tempdir().unwrap();
+        query_cmd(&["init", "-b", "develop"], temp_dir.path());
+        assert_eq!(None, default_branch(temp_dir.path()).unwrap());
+
+        query_cmd(&["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "init"], temp_dir.path());
+        assert_eq!(Some("develop".to_string()), default_branch(temp_dir.path()).unwrap());
+        assert!(!is_dirty(temp_dir.path()).unwrap());
+        assert_eq!(None, ahead_behind(temp_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn should_list_branches_with_tracking_state() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        let commit = || query_cmd(&["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "change"], path);
+        query_cmd(&["init", "-b", "main"], path);
+        commit();
+        query_cmd(&["remote", "add", "origin", "https://example.com/repo.git"], path);
+        query_cmd(&["update-ref", "refs/remotes/origin/main", "HEAD"], path);
+        query_cmd(&["update-ref", "refs/remotes/origin/old", "HEAD"], path);
+        query_cmd(&["branch", "-u", "origin/main", "main"], path);
+        query_cmd(&["branch", "--track", "old", "origin/old"], path);
+        query_cmd(&["update-ref", "-d", "refs/remotes/origin/old"], path);
+        commit();
+
+        let branches = branches(path).unwrap();
+        assert_eq!(vec!["main", "old"], branches.iter().map(|b| b.name.as_str()).collect::<Vec<_>>());
+        let main = &branches[0];
+        assert!(main.head && !main.gone);
+        assert_eq!((Some("origin/main"), 1, 0), (main.upstream.as_deref(), main.ahead, main.behind));
+        assert_eq!("Tester", main.author);
+        assert!(branches[1].gone && !branches[1].head);
+
+        assert_eq!(vec!["old"], merged_branches(path, "old").unwrap());
+        assert_eq!(vec!["main", "old"], merged_branches(path, "main").unwrap());
+
+        query_cmd(&["symbolic-ref", ORIGIN_HEAD, "refs/remotes/origin/main"], path);
+        let remote = remote_branches(path).unwrap();
+        assert_eq!(vec!["origin/main"], remote.iter().map(|b| b.name.as_str()).collect::<Vec<_>>());
+
+        delete_branch(path, "old", false).unwrap();
+        assert!(!ref_exists(path, "refs/heads/old"));
+    }
+
+    #[test]
+    fn should_skip_malformed_branch_lines() {
+        let line = " \x1frefs/heads/x\x1f\x1f\x1fabc1234\x1f1700000000\x1f2023-11-14\x1fJane";
+        assert_eq!(1700000000, parse_branch(line, LOCAL_REFS).unwrap().timestamp);
+        assert_eq!(None, parse_branch(&line.replace("1700000000", ""), LOCAL_REFS));
+        assert_eq!(None, parse_branch("refs/heads/x", LOCAL_REFS));
+    }
+
+    #[test]
+    fn should_list_branches_regardless_of_head_and_worktrees() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("repo");
+        fs::create_dir_all(&path).unwrap();
+        query_cmd(&["init", "-b", "main"], &path);
+        query_cmd(&["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "init"], &path);
+        let worktree = temp_dir.path().join("worktree");
+        query_cmd(&["worktree", "add", "-b", "feature/a+b", worktree.to_str().unwrap()], &path);
+        query_cmd(&["checkout", "-q", "--detach"], &path);
+
+        let branches = branches(&path).unwrap();
+        let names: Vec<_> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(vec!["feature/a+b", "main"], names);
+        assert!(branches.iter().all(|b| !b.head));
+        assert_eq!(branches[0].commit, branches[1].commit);
+    }
+
+    #[test]
+    fn should_read_head_state() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        let git = |args: &[&str]| {
+            let mut all = vec!["-c", "user.name=Tester", "-c", "user.email=tester@example.com"];
+            all.extend(args);
+            query_cmd(&all, path)
+        };
+        git(&["init", "-b", "main"]);
+        assert_eq!(HeadState::Unborn("main".to_string()), head_state(path).unwrap());
+
+        fs::write(path.join("file.txt"), "initial").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-m", "init"]);
+        assert_eq!(HeadState::Branch("main".to_string()), head_state(path).unwrap());
+
+        git(&["checkout", "-q", "--detach"]);
+        assert_eq!(HeadState::Detached(head_commit(path).unwrap()), head_state(path).unwrap());
+
+        git(&["checkout", "-q", "-b", "feature"]);
+        fs::write(path.join("file.txt"), "feature").unwrap();
+        git(&["commit", "-am", "feature"]);
+        git(&["checkout", "-q", "main"]);
+        fs::write(path.join("file.txt"), "main").unwrap();
+        git(&["commit", "-am", "main"]);
+        git(&["merge", "feature"]);
+        assert_eq!(HeadState::Merging, head_state(path).unwrap());
+        git(&["merge", "--abort"]);
+
+        git(&["rebase", "feature"]);
+        assert_eq!(HeadState::Rebasing, head_state(path).unwrap());
+        git(&["rebase", "--abort"]);
+
+        git(&["cherry-pick", "feature"]);
+        assert_eq!(HeadState::CherryPicking, head_state(path).unwrap());
+        assert_eq!(Some("git cherry-pick --abort"), head_state(path).unwrap().abort_command());
+        git(&["cherry-pick", "--abort"]);
+
+        fs::write(path.join("file.txt"), "main again").unwrap();
+        git(&["commit", "-am", "main again"]);
+        git(&["revert", "--no-edit", "HEAD~1"]);
+        assert_eq!(HeadState::Reverting, head_state(path).unwrap());
+        git(&["revert", "--abort"]);
+        git(&["reset", "-q", "--hard", "HEAD~1"]);
+
+        git(&["bisect", "start"]);
+        assert_eq!(HeadState::Bisecting, head_state(path).unwrap());
+        git(&["bisect", "reset"]);
+        assert_eq!(HeadState::Branch("main".to_string()), head_state(path).unwrap());
+    }
+
     fn validate_correct_branch(out: &Output, expected: &str) {
         match out.status.code() {
             Some(0) => {
@@ -0,0 +1,201 @@
+use std::cmp::Reverse;
+use std::io::Write;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::project::Project;
+use crate::{git, ERROR_WRITER};
+
+const SEPARATOR: char = '\x1f';
+const LOG_FORMAT: &str = "--format=%ct%x1f%cd%x1f%h%x1f%an%x1f%s";
+const DATE_FORMAT: &str = "--date=format:%Y-%m-%d %H:%M";
+
+/// Options narrowing down commits shown by `log`
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    /// Any date accepted by `git log --since`, e.g. `2.days` or `2024-01-31`
+    pub since: Option<String>,
+    pub author: Option<String>,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub timestamp: i64,
+    pub date: String,
+    pub repo: String,
+    pub hash: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// Commits of all repositories of the `projects` found in `root`, the newest first.
+/// Repositories which log can't be read are reported in the returned warnings
+pub fn timeline(
+    root: &Path,
+    projects: &[Project],
+    filter: &LogFilter,
+) -> (Vec<Entry>, Vec<String>) {
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+
+    for project in projects {
+        for repo in project.repos.iter().flatten() {
+            let name = project.repo_name(root, repo);
+            match read_log(&repo.path, filter) {
+                Ok(out) => entries.extend(parse(&out, &name)),
+                Err(e) => warnings.push(format!("{}: {}", name, e)),
+            }
+        }
+    }
+    entries.sort_by_key(|e| Reverse(e.timestamp));
+    (entries, warnings)
+}
+
+fn read_log(path: &Path, filter: &LogFilter) -> Result<String, git::GitError> {
+    let mut args = vec!["log".to_string(), LOG_FORMAT.to_string(), DATE_FORMAT.to_string()];
+    if let Some(since) = &filter.since {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(author) = &filter.author {
+        args.push(format!("--author={}", author));
+    }
+    if !filter.paths.is_empty() {
+        args.push("--".to_string());
+        args.extend(filter.paths.iter().cloned());
+    }
+
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let output = git::query_cmd(&args, path);
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        Some(code) => Err(git::GitError::ReadStateCode("log".to_string(), code)),
+        None => Err(git::GitError::ReadState("log".to_string())),
+    }
+}
+
+fn parse(out: &str, repo: &str) -> Vec<Entry> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, SEPARATOR);
+            Some(Entry {
+                timestamp: fields.next()?.parse().ok()?,
+                date: fields.next()?.to_string(),
+                hash: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                subject: fields.next().unwrap_or_default().to_string(),
+                repo: repo.to_string(),
+            })
+        })
+        .collect()
+}
+
+pub fn print_timeline(entries: &[Entry], mut writer: impl Write) {
+    let width = entries.iter().map(|e| e.repo.len()).max().unwrap_or_default();
+    for entry in entries {
+        writeln!(
+            writer,
+            "{} {} {} {}: {}",
+            entry.date,
+            format!("{:width$}", entry.repo, width = width).yellow(),
+            entry.hash.bright_green(),
+            entry.author,
+            entry.subject
+        )
+        .expect(ERROR_WRITER);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use crate::directory::Directory;
+
+    use super::*;
+
+    fn commit(path: &Path, file: &str, author: &str, date: &str) {
+        fs::write(path.join(file), file).unwrap();
+        let run = |args: &[&str]| {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(path)
+                .env("GIT_COMMITTER_DATE", date)
+                .env("GIT_AUTHOR_DATE", date)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+        };
+        run(&["add", "."]);
+        run(&["-c", &format!("user.name={}", author), "-c", "user.email=a@example.com",
+            "commit", "-m", &format!("add {}", file)]);
+    }
+
+    #[test]
+    fn should_parse_log_lines() {
+        let out = "1700000000\x1f2023-11-14 22:13\x1fabc1234\x1fJane\x1ffix: a\x1fb\n";
+        assert_eq!(
+            vec![Entry {
+                timestamp: 1700000000,
+                date: "2023-11-14 22:13".to_string(),
+                repo: "api".to_string(),
+                hash: "abc1234".to_string(),
+                author: "Jane".to_string(),
+                subject: "fix: a\x1fb".to_string(),
+            }],
+            parse(out, "api")
+        );
+        assert!(parse("not a log line", "api").is_empty());
+    }
+
+    #[test]
+    fn should_merge_repositories_chronologically() {
+        let temp_dir = tempdir().unwrap();
+        let api = temp_dir.path().join("api");
+        let web = temp_dir.path().join("web");
+        let empty = temp_dir.path().join("empty");
+        for repo in [&api, &web, &empty] {
+            fs::create_dir_all(repo).unwrap();
+            git::query_cmd(&["init", "-b", "main"], repo);
+        }
+        commit(&api, "a1.txt", "Jane", "2024-01-01T10:00:00");
+        commit(&web, "w1.txt", "John", "2024-01-02T10:00:00");
+        commit(&api, "a2.txt", "John", "2024-01-03T10:00:00");
+
+        let projects = vec![Project {
+            name: "project".to_string(),
+            path: temp_dir.path().join("project"),
+            repos: Some(vec![
+                Directory::from(api),
+                Directory::from(web),
+                Directory::from(empty),
+            ]),
+        }];
+
+        let (entries, warnings) = timeline(temp_dir.path(), &projects, &LogFilter::default());
+        let subjects: Vec<_> = entries.iter().map(|e| (e.repo.as_str(), e.subject.as_str())).collect();
+        assert_eq!(
+            vec![
+                ("project:api", "add a2.txt"),
+                ("project:web", "add w1.txt"),
+                ("project:api", "add a1.txt"),
+            ],
+            subjects
+        );
+        assert_eq!(1, warnings.len());
+
+        let filter = LogFilter {
+            author: Some("John".to_string()),
+            paths: vec!["a2.txt".to_string(), "w1.txt".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(2, timeline(temp_dir.path(), &projects, &filter).0.len());
+
+        let filter = LogFilter { since: Some("2024-01-02T12:00:00".to_string()), ..Default::default() };
+        assert_eq!(1, timeline(temp_dir.path(), &projects, &filter).0.len());
+    }
+}
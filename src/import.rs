@@ -0,0 +1,438 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+
+use crate::directory::{get_name, is_relative_inside};
+use crate::git;
+use crate::manifest::{Manifest, ProjectEntry, RepoEntry};
+
+const MR_CONFIG: &str = ".mrconfig";
+const REFS_HEADS: &str = "refs/heads/";
+/// Options of `git clone` followed by a separate value
+const CLONE_OPTIONS_WITH_VALUE: [&str; 17] = [
+    "-b", "--branch", "-o", "--origin", "-c", "--config", "-u", "--upload-pack", "-j", "--jobs",
+    "--depth", "--reference", "--reference-if-able", "--template", "--separate-git-dir",
+    "--shallow-since", "--shallow-exclude",
+];
+
+/// Repository lists of other multi-repo tools
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ImportFormat {
+    /// Google `repo` XML manifest
+    Repo,
+    /// `mr` configuration (.mrconfig)
+    Mr,
+    /// `gita` repositories CSV (repos.csv)
+    Gita,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        if get_name(path).eq(MR_CONFIG) {
+            return Some(Self::Mr);
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("xml") => Some(Self::Repo),
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Some(Self::Gita),
+            _ => None,
+        }
+    }
+}
+
+/// Single repository found in the imported file, `path` is relative to DEV_DIR or absolute
+#[derive(Debug, PartialEq)]
+struct Imported {
+    path: PathBuf,
+    name: Option<String>,
+    url: String,
+    branch: Option<String>,
+}
+
+/// Converts `content` of a file in given `format` into a manifest for DEV_DIR at `root`.
+/// Entries which can't be cloned are reported in the returned warnings
+pub fn import(content: &str, format: ImportFormat, root: &Path) -> Result<(Manifest, Vec<String>)> {
+    let mut warnings = Vec::new();
+    let imported = match format {
+        ImportFormat::Repo => parse_repo_xml(content, &mut warnings)?,
+        ImportFormat::Mr => parse_mrconfig(content, &mut warnings),
+        ImportFormat::Gita => parse_gita_csv(content, &mut warnings),
+    };
+    Ok((into_manifest(imported, root, &mut warnings), warnings))
+}
+
+pub fn import_file(
+    file: &Path,
+    format: Option<ImportFormat>,
+    root: &Path,
+) -> Result<(Manifest, Vec<String>)> {
+    let format = format.or_else(|| ImportFormat::from_path(file)).ok_or_else(|| {
+        anyhow!("Unable to determine format of {:?}, specify it with --from", file)
+    })?;
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {:?}", file))?;
+    import(&content, format, root).with_context(|| format!("Failed to import {:?}", file))
+}
+
+/// Groups repositories into projects by their parent directory, like `scan` does on disk.
+/// Repositories directly in DEV_DIR at `root` belong to a project named after it, those
+/// outside of `root` are reported in `warnings` and skipped
+fn into_manifest(imported: Vec<Imported>, root: &Path, warnings: &mut Vec<String>) -> Manifest {
+    let mut manifest = Manifest::default();
+
+    for repo in imported {
+        let path = repo.path.strip_prefix(root).unwrap_or(&repo.path).to_path_buf();
+        if !is_relative_inside(&path) {
+            warnings.push(format!("{:?}: not inside {:?}, skipping", repo.path, root));
+            continue;
+        }
+        let name = repo.name.unwrap_or_else(|| get_name(&path));
+        let project_name = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                parent.to_string_lossy().replace('\\', "/")
+            }
+            _ => get_name(root),
+        };
+
+        let default_target = Path::new(&project_name).join(&name);
+        let entry = RepoEntry {
+            url: repo.url,
+            branch: repo.branch,
+            path: (default_target != path).then(|| path.to_string_lossy().to_string()),
+            name,
+        };
+
+        match manifest.projects.iter_mut().find(|p| p.name == project_name) {
+            Some(project) => project.repos.push(entry),
+            None => manifest.projects.push(ProjectEntry {
+                name: project_name,
+                repos: vec![entry],
+            }),
+        }
+    }
+    manifest
+}
+
+/// Google `repo` manifest: `<remote>`, `<default>` and `<project>` elements
+fn parse_repo_xml(content: &str, warnings: &mut Vec<String>) -> Result<Vec<Imported>> {
+    let doc = roxmltree::Document::parse(content)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "manifest" {
+        return Err(anyhow!("Expected <manifest> root element"));
+    }
+
+    let elements = |tag: &'static str| root.children().filter(move |n| n.has_tag_name(tag));
+    let default = elements("default").next();
+    let default_remote = default.and_then(|d| d.attribute("remote"));
+    let default_revision = default.and_then(|d| d.attribute("revision"));
+
+    for include in elements("include") {
+        warnings.push(format!(
+            "Included manifest {} is not supported, import it separately",
+            include.attribute("name").unwrap_or_default()
+        ));
+    }
+
+    let mut imported = Vec::new();
+    for project in elements("project") {
+        let Some(name) = project.attribute("name") else {
+            warnings.push("Project without a name, skipping".to_string());
+            continue;
+        };
+        let remote_name = project.attribute("remote").or(default_remote);
+        let fetch = elements("remote")
+            .find(|r| r.attribute("name") == remote_name)
+            .and_then(|r| r.attribute("fetch"));
+
+        let url = match fetch {
+            Some(fetch) if !fetch.starts_with('.') => {
+                format!("{}/{}", fetch.trim_end_matches('/'), name)
+            }
+            Some(fetch) => {
+                warnings.push(format!(
+                    "{}: fetch URL '{}' is relative to the manifest repository, skipping",
+                    name, fetch
+                ));
+                continue;
+            }
+            None => {
+                warnings.push(format!("{}: remote {:?} is not defined, skipping", name, remote_name));
+                continue;
+            }
+        };
+
+        let revision = project.attribute("revision").or(default_revision);
+        imported.push(Imported {
+            path: PathBuf::from(project.attribute("path").unwrap_or(name)),
+            name: None,
+            url,
+            branch: revision.and_then(branch_of_revision),
+        });
+    }
+    Ok(imported)
+}
+
+/// Revision can be a branch, `refs/heads/<branch>`, a tag or a commit, only branches are kept
+fn branch_of_revision(revision: &str) -> Option<String> {
+    if let Some(branch) = revision.strip_prefix(REFS_HEADS) {
+        return Some(branch.to_string());
+    }
+    let is_commit = revision.len() == 40 && revision.chars().all(|c| c.is_ascii_hexdigit());
+    if is_commit || revision.starts_with("refs/") {
+        None
+    } else {
+        Some(revision.to_string())
+    }
+}
+
+/// `mr` config: sections named by repository path with a `checkout = git clone <url> <dir>` command
+fn parse_mrconfig(content: &str, warnings: &mut Vec<String>) -> Vec<Imported> {
+    let mut imported = Vec::new();
+    let mut section: Option<String> = None;
+    let mut checkout: Option<String> = None;
+
+    let mut finish = |section: Option<String>, checkout: Option<String>, warnings: &mut Vec<String>| {
+        let Some(section) = section.filter(|s| s != "DEFAULT") else { return };
+        match checkout.as_deref().and_then(parse_git_clone) {
+            Some((url, branch)) => imported.push(Imported {
+                path: PathBuf::from(&section),
+                name: None,
+                url,
+                branch,
+            }),
+            None => warnings.push(format!("{}: no 'git clone' checkout command, skipping", section)),
+        }
+    };
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            finish(section.take(), checkout.take(), warnings);
+            section = Some(name.trim().to_string());
+        } else if let Some((key, value)) = trimmed.split_once('=') {
+            if key.trim() == "checkout" {
+                checkout = Some(value.trim().to_string());
+            }
+        }
+    }
+    finish(section, checkout, warnings);
+    imported
+}
+
+/// URL and branch from a `git clone [-b <branch>] <url> [<dir>]` shell command
+fn parse_git_clone(command: &str) -> Option<(String, Option<String>)> {
+    let words = shell_words(command);
+    let clone = words.windows(2).position(|w| w[0] == "git" && w[1] == "clone")?;
+
+    let mut branch = None;
+    let mut args = words[clone + 2..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-b" | "--branch" => branch = args.next().cloned(),
+            a if CLONE_OPTIONS_WITH_VALUE.contains(&a) => {
+                args.next();
+            }
+            a if a.starts_with('-') => {}
+            url => return Some((url.to_string(), branch)),
+        }
+    }
+    None
+}
+
+fn shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() || c == ';' || c == '&' => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+/// `gita` CSV: `path,name[,type,flags]` lines. Remote URL is read from the repository on disk
+fn parse_gita_csv(content: &str, warnings: &mut Vec<String>) -> Vec<Imported> {
+    content
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|f| f.trim());
+            let path = PathBuf::from(fields.next()?);
+            let name = fields.next().filter(|n| !n.is_empty()).map(|n| n.to_string());
+
+            let url = path
+                .is_dir()
+                .then(|| git::remotes(&path).ok())
+                .flatten()
+                .and_then(|remotes| {
+                    remotes.iter().find(|r| r.as_str() == "origin").or(remotes.first()).cloned()
+                })
+                .and_then(|remote| git::remote_url(&path, &remote).ok());
+            match url {
+                Some(url) => Some(Imported {
+                    branch: git::current_branch(&path).ok().filter(|b| !b.is_empty()),
+                    path,
+                    name,
+                    url,
+                }),
+                None => {
+                    warnings.push(format!("{:?}: unable to read remote URL, skipping", path));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const REPO_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <manifest>
+          <remote name="origin" fetch="https://example.com/org/" />
+          <remote name="local" fetch=".." />
+          <default remote="origin" revision="main" />
+          <project name="api" path="clients/api" />
+          <project name="platform/web" path="clients/web" revision="refs/heads/develop" />
+          <project name="tools" revision="0123456789abcdef0123456789abcdef01234567" />
+          <project name="relative" remote="local" />
+          <include name="other.xml" />
+        </manifest>"#;
+
+    #[test]
+    fn should_detect_format() {
+        assert_eq!(Some(ImportFormat::Mr), ImportFormat::from_path(Path::new("/home/u/.mrconfig")));
+        assert_eq!(Some(ImportFormat::Repo), ImportFormat::from_path(Path::new("default.xml")));
+        assert_eq!(Some(ImportFormat::Gita), ImportFormat::from_path(Path::new("repos.csv")));
+        assert_eq!(None, ImportFormat::from_path(Path::new("repos.txt")));
+    }
+
+    #[test]
+    fn should_import_repo_xml() {
+        let (manifest, warnings) = import(REPO_XML, ImportFormat::Repo, Path::new("/dev")).unwrap();
+
+        assert_eq!(2, manifest.projects.len());
+        let clients = &manifest.projects[0];
+        assert_eq!("clients", clients.name);
+        assert_eq!("https://example.com/org/api", clients.repos[0].url);
+        assert_eq!(Some("main".to_string()), clients.repos[0].branch);
+        assert_eq!("web", clients.repos[1].name);
+        assert_eq!("https://example.com/org/platform/web", clients.repos[1].url);
+        assert_eq!(Some("develop".to_string()), clients.repos[1].branch);
+        assert_eq!(None, clients.repos[1].path);
+
+        let top_level = &manifest.projects[1];
+        assert_eq!("dev", top_level.name);
+        assert_eq!("tools", top_level.repos[0].name);
+        assert_eq!(Some("tools".to_string()), top_level.repos[0].path);
+        assert_eq!(None, top_level.repos[0].branch);
+
+        assert_eq!(2, warnings.len());
+    }
+
+    #[test]
+    fn should_reject_other_xml() {
+        assert!(import("<project/>", ImportFormat::Repo, Path::new("/dev")).is_err());
+    }
+
+    #[test]
+    fn should_import_mrconfig() {
+        let content = r#"
+            [DEFAULT]
+            lib = echo
+
+            [src/api]
+            checkout = git clone 'git@example.com:org/api.git' 'api'
+
+            # comment
+            [src/web]
+            checkout = git clone -b develop "https://example.com/org/web.git" web-app
+
+            [src/svn]
+            checkout = svn co https://example.com/svn
+        "#;
+        let (manifest, warnings) = import(content, ImportFormat::Mr, Path::new("/dev")).unwrap();
+
+        assert_eq!(1, manifest.projects.len());
+        let src = &manifest.projects[0];
+        assert_eq!("src", src.name);
+        assert_eq!("git@example.com:org/api.git", src.repos[0].url);
+        assert_eq!("https://example.com/org/web.git", src.repos[1].url);
+        assert_eq!(Some("develop".to_string()), src.repos[1].branch);
+        assert_eq!(vec!["src/svn: no 'git clone' checkout command, skipping"], warnings);
+    }
+
+    #[test]
+    fn should_parse_git_clone() {
+        assert_eq!(
+            Some(("url".to_string(), Some("main".to_string()))),
+            parse_git_clone("cd x && git clone --depth 1 --branch main url dir")
+        );
+        assert_eq!(
+            Some(("url".to_string(), None)),
+            parse_git_clone("git clone -c core.autocrlf=false --config user.name=x url")
+        );
+        assert_eq!(None, parse_git_clone("git clone"));
+        assert_eq!(None, parse_git_clone("git fetch url"));
+    }
+
+    #[test]
+    fn should_import_gita_csv() {
+        let temp_dir = tempdir().unwrap();
+        let api = temp_dir.path().join("clients/api");
+        fs::create_dir_all(&api).unwrap();
+        git::query_cmd(&["init", "-b", "main"], &api);
+        git::query_cmd(&["remote", "add", "origin", "git@example.com:org/api.git"], &api);
+
+        let outside = tempdir().unwrap();
+        git::query_cmd(&["init", "-b", "main"], outside.path());
+        git::query_cmd(&["remote", "add", "origin", "git@example.com:org/x.git"], outside.path());
+
+        let content = format!(
+            "{},backend-api,,\n{},missing,,\n{},outside,,\n",
+            api.display(),
+            temp_dir.path().join("missing").display(),
+            outside.path().display()
+        );
+        let (manifest, warnings) = import(&content, ImportFormat::Gita, temp_dir.path()).unwrap();
+
+        assert_eq!(1, manifest.projects.len());
+        assert_eq!("clients", manifest.projects[0].name);
+        let repo = &manifest.projects[0].repos[0];
+        assert_eq!("backend-api", repo.name);
+        assert_eq!("git@example.com:org/api.git", repo.url);
+        assert_eq!(Some("clients/api".to_string()), repo.path);
+        assert_eq!(2, warnings.len());
+        assert!(warnings[1].contains("not inside"), "{:?}", warnings);
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::git::{self, Branch};
+use crate::ERROR_WRITER;
+
+const ORIGIN: &str = "origin";
+const MISSING: &str = "-";
+const LEGEND: &str = "* checked out, = in sync, +N/-N commits ahead/behind upstream, \
+    local: no upstream, gone: upstream deleted, merged/unmerged: into the default branch";
+
+/// Local branches of a single repository
+#[derive(Debug)]
+pub struct RepoBranches {
+    pub repo: String,
+    pub default: Option<String>,
+    pub branches: Vec<Branch>,
+    /// Branches merged into the default branch
+    pub merged: Vec<String>,
+}
+
+pub fn collect(path: &Path, repo: &str) -> Result<RepoBranches, git::GitError> {
+    let branches = git::branches(path)?;
+    let default = git::default_branch(path)?;
+    let merged = match &default {
+        Some(default) => match merge_target(path, default) {
+            Some(target) => git::merged_branches(path, &target)?,
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+
+    Ok(RepoBranches {
+        repo: repo.to_string(),
+        default,
+        branches,
+        merged,
+    })
+}
+
+/// The local default branch, otherwise its counterpart on `origin`
+fn merge_target(path: &Path, default: &str) -> Option<String> {
+    [format!("refs/heads/{}", default), format!("refs/remotes/{}/{}", ORIGIN, default)]
+        .into_iter()
+        .find(|reference| git::ref_exists(path, reference))
+}
+
+/// Last commit date, tracking and merge state of the `branch`
+pub fn describe(repo: &RepoBranches, branch: &Branch) -> String {
+    let tracking = match (&branch.upstream, branch.gone, branch.ahead, branch.behind) {
+        (None, _, _, _) => "local".to_string(),
+        (Some(_), true, _, _) => "gone".to_string(),
+        (Some(_), false, 0, 0) => "=".to_string(),
+        (Some(_), false, 0, behind) => format!("-{}", behind),
+        (Some(_), false, ahead, 0) => format!("+{}", ahead),
+        (Some(_), false, ahead, behind) => format!("+{}/-{}", ahead, behind),
+    };
+    let head = if branch.head { " *" } else { "" };
+    let merged = if repo.default.as_ref() == Some(&branch.name) || repo.default.is_none() {
+        ""
+    } else if repo.merged.contains(&branch.name) {
+        " merged"
+    } else {
+        " unmerged"
+    };
+    format!("{} {}{}{}", branch.date, tracking, merged, head)
+}
+
+/// Prints a table with a row for every branch and a column for every repository
+pub fn print_matrix(repos: &[RepoBranches], mut writer: impl Write) {
+    let names: BTreeSet<&str> = repos
+        .iter()
+        .flat_map(|r| r.branches.iter().map(|b| b.name.as_str()))
+        .collect();
+    let rows: Vec<(&str, Vec<String>)> = names
+        .into_iter()
+        .map(|name| {
+            let cells = repos
+                .iter()
+                .map(|repo| match repo.branches.iter().find(|b| b.name == name) {
+                    Some(branch) => describe(repo, branch),
+                    None => MISSING.to_string(),
+                })
+                .collect();
+            (name, cells)
+        })
+        .collect();
+
+    let first_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or_default().max(6);
+    let widths: Vec<usize> = repos
+        .iter()
+        .enumerate()
+        .map(|(i, repo)| {
+            rows.iter()
+                .map(|(_, cells)| cells[i].len())
+                .max()
+                .unwrap_or_default()
+                .max(repo.repo.len())
+        })
+        .collect();
+
+    let mut header = format!("{:width$}", "Branch", width = first_width);
+    for (repo, width) in repos.iter().zip(&widths) {
+        header += &format!("  {}", format!("{:width$}", repo.repo, width = width).yellow());
+    }
+    writeln!(writer, "{}", header.trim_end()).expect(ERROR_WRITER);
+
+    for (name, cells) in &rows {
+        let mut line = format!("{:width$}", name, width = first_width).bright_green().to_string();
+        for (cell, width) in cells.iter().zip(&widths) {
+            line += &format!("  {:width$}", cell, width = width);
+        }
+        writeln!(writer, "{}", line.trim_end()).expect(ERROR_WRITER);
+    }
+    writeln!(writer, "\n{}", LEGEND).expect(ERROR_WRITER);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn commit(path: &Path) {
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "change"]);
+    }
+
+    fn branch(name: &str, head: bool) -> Branch {
+        Branch {
+            name: name.to_string(),
+            head,
+            upstream: None,
+            gone: false,
+            ahead: 0,
+            behind: 0,
+            commit: "abc1234".to_string(),
+            timestamp: 0,
+            date: "2024-01-31".to_string(),
+            author: "Tester".to_string(),
+        }
+    }
+
+    #[test]
+    fn should_describe_branches() {
+        let repo = RepoBranches {
+            repo: "api".to_string(),
+            default: Some("main".to_string()),
+            branches: Vec::new(),
+            merged: vec!["main".to_string(), "done".to_string()],
+        };
+        let tracked = Branch {
+            upstream: Some("origin/main".to_string()),
+            ahead: 2,
+            behind: 1,
+            ..branch("main", true)
+        };
+        let gone = Branch { upstream: Some("origin/done".to_string()), gone: true, ..branch("done", false) };
+
+        assert_eq!("2024-01-31 +2/-1 *", describe(&repo, &tracked));
+        assert_eq!("2024-01-31 gone merged", describe(&repo, &gone));
+        assert_eq!("2024-01-31 local unmerged", describe(&repo, &branch("wip", false)));
+    }
+
+    #[test]
+    fn should_collect_merged_branches() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        commit(path);
+        git(path, &["branch", "done"]);
+        git(path, &["checkout", "-b", "wip"]);
+        commit(path);
+
+        let repo = collect(path, "api").unwrap();
+        assert_eq!(Some("main".to_string()), repo.default);
+        assert_eq!(vec!["done", "main"], repo.merged);
+        assert_eq!(3, repo.branches.len());
+        assert!(repo.branches.iter().all(|b| b.head == (b.name == "wip") && b.upstream.is_none()));
+
+        let mut out = Vec::new();
+        print_matrix(&[repo], &mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("unmerged"), "{}", out);
+    }
+}
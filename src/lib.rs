@@ -1,7 +1,27 @@
+pub mod branching;
+pub mod bulk;
 pub mod commands;
+pub mod commit;
+pub mod config;
+pub mod diff;
 pub mod directory;
+pub mod doctor;
+pub mod filter;
 pub mod project;
+pub mod push;
+pub mod remote;
 pub mod git;
+pub mod history;
+pub mod import;
+pub mod inventory;
+pub mod maintain;
+pub mod manifest;
+pub mod search;
+pub mod selector;
+pub mod snapshot;
+pub mod stale;
+pub mod stash;
+pub mod usage;
 
 pub const DEFAULT_DEV_DIR_VAR: &str = "DEV_DIR";
 pub const ERROR_WRITER: &str = "Failed to write to the output!";
\ No newline at end of file
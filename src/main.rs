@@ -5,7 +5,13 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
 
-use please::commands::{Commands, handle_clean, handle_list, handle_pull, handle_status};
+use please::commands::{
+    handle_branch, handle_branches, handle_clean, handle_commit, handle_diff, handle_doctor,
+    handle_export, handle_grep, handle_import, handle_list, handle_log, handle_maintain,
+    handle_pull, handle_push, handle_remote, handle_snapshot, handle_stale, handle_stash,
+    handle_status, handle_switch, handle_sync, Commands,
+};
+use please::history::LogFilter;
 use please::DEFAULT_DEV_DIR_VAR;
 
 #[derive(Parser)]
@@ -27,18 +33,62 @@ struct Cli {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    resolve_path(&cli.override_default, &cli.path).and_then(|path| match &cli.command {
-        Some(Commands::List) => handle_list(&path, &mut std::io::stdout()),
-        Some(Commands::Status { name }) => handle_status(&path, name),
-        Some(Commands::Pull { name }) => handle_pull(&path, name),
-        Some(Commands::Clean) => handle_clean(),
+    let path = resolve_path(&cli.override_default, &cli.path);
+    match &cli.command {
+        Some(Commands::Doctor { target, fsck }) => {
+            let var = cli.override_default.as_deref().unwrap_or(DEFAULT_DEV_DIR_VAR);
+            handle_doctor(path.ok().as_deref(), var, target, *fsck)
+        }
+        Some(Commands::List { size }) => handle_list(&path?, *size, &mut std::io::stdout()),
+        Some(Commands::Status { target }) => handle_status(&path?, target),
+        Some(Commands::Pull { target }) => handle_pull(&path?, target),
+        Some(Commands::Branch { target, action }) => handle_branch(&path?, target, action),
+        Some(Commands::Branches { target }) => handle_branches(&path?, target),
+        Some(Commands::Stale { target, older_than, local, delete, force }) => {
+            handle_stale(&path?, target, *older_than, *local, *delete, *force)
+        }
+        Some(Commands::Switch { target, branch }) => handle_switch(&path?, target, branch),
+        Some(Commands::Commit { target, message }) => handle_commit(&path?, target, message),
+        Some(Commands::Push { target, set_upstream }) => handle_push(&path?, target, *set_upstream),
+        Some(Commands::Stash { target, action }) => handle_stash(&path?, target, action),
+        Some(Commands::Log { target, since, author, paths }) => {
+            let filter = LogFilter {
+                since: Some(since.clone()),
+                author: author.clone(),
+                paths: paths.clone(),
+            };
+            handle_log(&path?, target, filter)
+        }
+        Some(Commands::Grep { target, pattern, ignore_case }) => {
+            handle_grep(&path?, target, pattern, *ignore_case)
+        }
+        Some(Commands::Diff { target, staged, stat }) => {
+            handle_diff(&path?, target, *staged, *stat)
+        }
+        Some(Commands::Remote { target, dry_run, action }) => {
+            handle_remote(&path?, target, action, *dry_run)
+        }
+        Some(Commands::Maintain { target, aggressive }) => {
+            handle_maintain(&path?, target, *aggressive)
+        }
+        Some(Commands::Snapshot { action }) => handle_snapshot(&path?, action),
+        Some(Commands::Sync { manifest }) => handle_sync(&path?, manifest, &mut std::io::stdout()),
+        Some(Commands::Export { output, format }) => handle_export(&path?, output, format),
+        Some(Commands::Import { file, from, output, format }) => {
+            handle_import(&path?, file, from, output, format)
+        }
+        Some(Commands::Clean) => {
+            path?;
+            handle_clean()
+        }
         None => {
+            path?;
             println!(
                 "No command given. Use with --help or -h to see available commands and options"
             );
             Ok(())
         }
-    })
+    }
 }
 
 fn resolve_path(override_default: &Option<String>, path_arg: &Option<PathBuf>) -> Result<PathBuf> {
@@ -0,0 +1,138 @@
+use std::io::Write;
+use std::ops::AddAssign;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::directory::{dir_size, format_size};
+use crate::{git, ERROR_WRITER};
+
+/// Size of the `.git` directory before and after maintenance
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Sizes {
+    pub before: u64,
+    pub after: u64,
+}
+
+impl Sizes {
+    /// Bytes freed by the maintenance, zero when the directory grew
+    pub fn reclaimed(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl AddAssign for Sizes {
+    fn add_assign(&mut self, other: Sizes) {
+        self.before += other.before;
+        self.after += other.after;
+    }
+}
+
+/// Runs maintenance in the repository at `path` and prunes remote-tracking branches
+/// deleted on its remotes, see [git::maintenance]. Remotes which can't be pruned,
+/// e.g. when offline, are reported in the returned warnings
+pub fn maintain(path: &Path, aggressive: bool) -> Result<(Sizes, Vec<String>), git::GitError> {
+    let git_dir = git::git_dir(path)?;
+    let before = dir_size(&git_dir);
+    git::maintenance(path, aggressive)?;
+    let mut warnings = Vec::new();
+    match git::remotes(path) {
+        Ok(remotes) => {
+            for remote in remotes {
+                if let Err(e) = git::prune_remote(path, &remote) {
+                    warnings.push(format!("{}: {}", remote, e));
+                }
+            }
+        }
+        Err(e) => warnings.push(e.to_string()),
+    }
+    Ok((Sizes { before, after: dir_size(&git_dir) }, warnings))
+}
+
+pub fn describe(sizes: &Sizes) -> String {
+    format!(
+        "{} -> {}, reclaimed {}",
+        format_size(sizes.before),
+        format_size(sizes.after),
+        format_size(sizes.reclaimed()).bright_green()
+    )
+}
+
+/// Prints `.git` sizes per repository followed by totals
+pub fn print_summary(sizes: &[(String, Sizes)], mut writer: impl Write) {
+    let width = sizes.iter().map(|(repo, _)| repo.len()).max().unwrap_or_default().max(5);
+    let mut total = Sizes::default();
+    for (repo, repo_sizes) in sizes {
+        let repo = format!("{:width$}", repo, width = width);
+        writeln!(writer, "{} {}", repo.yellow(), describe(repo_sizes)).expect(ERROR_WRITER);
+        total += *repo_sizes;
+    }
+    writeln!(writer, "{:width$} {}", "Total", describe(&total), width = width)
+        .expect(ERROR_WRITER);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn commit(path: &Path, message: &str) {
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", message]);
+    }
+
+    #[test]
+    fn should_compute_reclaimed_space() {
+        assert_eq!(300, Sizes { before: 1000, after: 700 }.reclaimed());
+        assert_eq!(0, Sizes { before: 700, after: 1000 }.reclaimed());
+
+        let mut out = Vec::new();
+        print_summary(&[("api".to_string(), Sizes { before: 2048, after: 1024 })], &mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("2.0 KiB -> 1.0 KiB"), "{}", out);
+        assert!(out.contains("Total"), "{}", out);
+    }
+
+    #[test]
+    fn should_pack_objects_and_prune_remotes() {
+        let temp_dir = tempdir().unwrap();
+        let origin = temp_dir.path().join("origin");
+        let local = temp_dir.path().join("local");
+        fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "-b", "main"]);
+        commit(&origin, "init");
+        git(&origin, &["branch", "feature"]);
+        git(temp_dir.path(), &["clone", "-q", "origin", "local"]);
+        git(&origin, &["branch", "-D", "feature"]);
+        for i in 0..20 {
+            fs::write(local.join("file.txt"), format!("version {}", i)).unwrap();
+            git(&local, &["add", "."]);
+            commit(&local, &format!("change {}", i));
+        }
+
+        for aggressive in [false, true] {
+            let (sizes, warnings) = maintain(&local, aggressive).unwrap();
+            assert!(sizes.before > 0 && sizes.after > 0, "{:?}", sizes);
+            assert!(warnings.is_empty(), "{:?}", warnings);
+        }
+        assert!(!git::ref_exists(&local, "refs/remotes/origin/feature"));
+        let objects = git::query_cmd(&["count-objects", "-v"], &local);
+        let objects = String::from_utf8_lossy(&objects.stdout);
+        assert!(objects.contains("count: 0"), "{}", objects);
+
+        git(&local, &["remote", "add", "offline", "/nonexistent/repository"]);
+        let (sizes, warnings) = maintain(&local, false).unwrap();
+        assert!(sizes.after > 0, "{:?}", sizes);
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].starts_with("offline: "), "{:?}", warnings);
+    }
+}
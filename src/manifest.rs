@@ -0,0 +1,367 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::directory::{is_relative_inside, GIT_DIR};
+use crate::project::{scan, Project};
+use crate::{git, ERROR_WRITER};
+
+pub const MANIFEST_FILE: &str = "please-manifest.toml";
+const ORIGIN: &str = "origin";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ManifestFormat {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ManifestFormat {
+    /// Format based on the file extension, TOML for unknown extensions
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                Self::Yaml
+            }
+            _ => Self::Toml,
+        }
+    }
+}
+
+/// Declared layout of the workspace: projects and Git repositories they should contain
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default, rename = "project")]
+    pub projects: Vec<ProjectEntry>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectEntry {
+    /// Directory of the project relative to DEV_DIR, e.g. `clients` or `clients/api`
+    pub name: String,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<RepoEntry>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoEntry {
+    pub name: String,
+    pub url: String,
+    /// Branch checked out after cloning, remote's default when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Target directory relative to DEV_DIR, `<project>/<name>` when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl RepoEntry {
+    /// Directory to clone the repository into. Absolute paths and paths with `..`
+    /// are rejected, so that nothing is cloned outside of `root`
+    pub fn target(&self, root: &Path, project: &ProjectEntry) -> Result<PathBuf> {
+        let relative = match &self.path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(&project.name).join(&self.name),
+        };
+        if !is_relative_inside(&relative) {
+            return Err(anyhow!("{:?} is not a relative path inside DEV_DIR", relative));
+        }
+        Ok(root.join(relative))
+    }
+}
+
+pub fn load(path: &Path) -> Result<Manifest> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read manifest at {:?}", path))?;
+    parse_as(&content, ManifestFormat::from_path(path))
+        .with_context(|| format!("Invalid manifest at {:?}", path))
+}
+
+pub fn parse(content: &str) -> Result<Manifest> {
+    parse_as(content, ManifestFormat::Toml)
+}
+
+pub fn parse_as(content: &str, format: ManifestFormat) -> Result<Manifest> {
+    match format {
+        ManifestFormat::Toml => Ok(toml::from_str(content)?),
+        ManifestFormat::Json => Ok(serde_json::from_str(content)?),
+        ManifestFormat::Yaml => Ok(serde_yaml::from_str(content)?),
+    }
+}
+
+pub fn to_string(manifest: &Manifest, format: ManifestFormat) -> Result<String> {
+    match format {
+        ManifestFormat::Toml => Ok(toml::to_string(manifest)?),
+        ManifestFormat::Json => Ok(serde_json::to_string_pretty(manifest)? + "\n"),
+        ManifestFormat::Yaml => Ok(serde_yaml::to_string(manifest)?),
+    }
+}
+
+/// Describes scanned `projects` as a manifest, reading remote URL (`origin` or the first one)
+/// and current branch of every repository. Repositories without remotes are reported in
+/// the returned warnings, as they can't be cloned from the manifest
+pub fn export(root: &Path, projects: &[Project]) -> (Manifest, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut manifest = Manifest::default();
+
+    for project in projects {
+        let Some(repos) = &project.repos else { continue };
+        let mut entry = ProjectEntry {
+            name: project.qualified_name(root),
+            repos: Vec::new(),
+        };
+
+        for repo in repos {
+            let remotes = git::remotes(&repo.path).unwrap_or_default();
+            let remote = remotes
+                .iter()
+                .find(|r| r.as_str() == ORIGIN)
+                .or_else(|| remotes.first());
+            let Some(url) = remote.and_then(|r| git::remote_url(&repo.path, r).ok()) else {
+                warnings.push(format!("{} {:?} has no remote, skipping", repo.name, repo.path));
+                continue;
+            };
+
+            let default_target = root.join(&entry.name).join(&repo.name);
+            entry.repos.push(RepoEntry {
+                name: repo.name.clone(),
+                url,
+                branch: git::current_branch(&repo.path).ok().filter(|b| !b.is_empty()),
+                path: (default_target != repo.path).then(|| {
+                    repo.path
+                        .strip_prefix(root)
+                        .unwrap_or(&repo.path)
+                        .to_string_lossy()
+                        .to_string()
+                }),
+            });
+        }
+
+        if !entry.repos.is_empty() {
+            manifest.projects.push(entry);
+        }
+    }
+    (manifest, warnings)
+}
+
+/// Clones repositories from the `manifest` missing in `root`
+/// and warns about the ones found on disk, but not declared in the manifest
+pub fn sync(root: &Path, manifest: &Manifest, mut writer: impl Write) -> Result<()> {
+    let mut declared = HashSet::new();
+    let mut failed = 0;
+
+    for project in &manifest.projects {
+        writeln!(writer, "Project {}", project.name.bright_green()).expect(ERROR_WRITER);
+        for repo in &project.repos {
+            let target = match repo.target(root, project) {
+                Ok(target) => target,
+                Err(e) => {
+                    writeln!(writer, "{} {}: {}, skipping", "=>".red(), repo.name.yellow(), e)
+                        .expect(ERROR_WRITER);
+                    failed += 1;
+                    continue;
+                }
+            };
+            declared.insert(target.clone());
+
+            if target.join(GIT_DIR).exists() {
+                writeln!(writer, "{} {}: already present", "=>".bright_green(), repo.name.yellow())
+                    .expect(ERROR_WRITER);
+                continue;
+            }
+            let occupied = target.exists()
+                && fs::read_dir(&target).map_or(true, |mut entries| entries.next().is_some());
+            if occupied {
+                writeln!(writer, "{} {}: {:?} exists and is not a Git repository, skipping",
+                         "=>".red(), repo.name.yellow(), target)
+                    .expect(ERROR_WRITER);
+                failed += 1;
+                continue;
+            }
+
+            match git::clone(&repo.url, repo.branch.as_deref(), &target) {
+                Ok(()) => writeln!(writer, "{} {}: cloned into {:?}",
+                                   "=>".bright_green(), repo.name.yellow(), target),
+                Err(e) => {
+                    failed += 1;
+                    writeln!(writer, "{} {}: {}", "=>".red(), repo.name.yellow(), e)
+                }
+            }.expect(ERROR_WRITER);
+        }
+    }
+
+    for project in scan(root).unwrap_or_default() {
+        for repo in project.repos.into_iter().flatten() {
+            if !declared.contains(&repo.path) {
+                writeln!(writer, "{} {} {:?} is not declared in the manifest",
+                         "Warning:".yellow(), repo.name.yellow(), repo.path)
+                    .expect(ERROR_WRITER);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("Failed to sync {} repositories", failed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [[project]]
+        name = "clients"
+
+        [[project.repo]]
+        name = "api"
+        url = "git@example.com:org/api.git"
+        branch = "develop"
+
+        [[project.repo]]
+        name = "web"
+        url = "git@example.com:org/web.git"
+        path = "frontend/web-app"
+    "#;
+
+    #[test]
+    fn should_parse_manifest() {
+        let manifest = parse(MANIFEST).unwrap();
+
+        assert_eq!(1, manifest.projects.len());
+        let project = &manifest.projects[0];
+        assert_eq!("clients", project.name);
+        assert_eq!(2, project.repos.len());
+        assert_eq!(Some("develop".to_string()), project.repos[0].branch);
+        assert_eq!(None, project.repos[1].branch);
+    }
+
+    #[test]
+    fn should_reject_repo_without_url() {
+        assert!(parse("[[project]]\nname = \"p\"\n[[project.repo]]\nname = \"r\"").is_err());
+    }
+
+    #[test]
+    fn should_resolve_targets() {
+        let manifest = parse(MANIFEST).unwrap();
+        let project = &manifest.projects[0];
+        let root = Path::new("/dev");
+
+        assert_eq!(PathBuf::from("/dev/clients/api"), project.repos[0].target(root, project).unwrap());
+        assert_eq!(
+            PathBuf::from("/dev/frontend/web-app"),
+            project.repos[1].target(root, project).unwrap()
+        );
+
+        for path in ["/tmp/api", "../api", "clients/../../api", ""] {
+            let repo = RepoEntry {
+                name: "api".to_string(),
+                url: "git@example.com:org/api.git".to_string(),
+                branch: None,
+                path: Some(path.to_string()),
+            };
+            assert!(repo.target(root, project).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn should_detect_format() {
+        assert_eq!(ManifestFormat::Json, ManifestFormat::from_path(Path::new("a/m.JSON")));
+        assert_eq!(ManifestFormat::Yaml, ManifestFormat::from_path(Path::new("m.yml")));
+        assert_eq!(ManifestFormat::Yaml, ManifestFormat::from_path(Path::new("m.yaml")));
+        assert_eq!(ManifestFormat::Toml, ManifestFormat::from_path(Path::new("m.toml")));
+        assert_eq!(ManifestFormat::Toml, ManifestFormat::from_path(Path::new("manifest")));
+    }
+
+    #[test]
+    fn should_round_trip_all_formats() {
+        let manifest = parse(MANIFEST).unwrap();
+        for format in [ManifestFormat::Toml, ManifestFormat::Json, ManifestFormat::Yaml] {
+            let content = to_string(&manifest, format).unwrap();
+            assert_eq!(manifest, parse_as(&content, format).unwrap(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn should_export_repositories_with_remotes() {
+        let temp_dir = tempdir().unwrap();
+        let api = temp_dir.path().join("clients/api");
+        let local = temp_dir.path().join("clients/local");
+        for repo in [&api, &local] {
+            fs::create_dir_all(repo).unwrap();
+            git::query_cmd(&["init", "-b", "main"], repo);
+        }
+        git::query_cmd(&["remote", "add", "origin", "git@example.com:org/api.git"], &api);
+
+        let projects = scan(temp_dir.path()).unwrap();
+        let (manifest, warnings) = export(temp_dir.path(), &projects);
+
+        assert_eq!(
+            parse(
+                "[[project]]\nname = \"clients\"\n[[project.repo]]\nname = \"api\"\n\
+                url = \"git@example.com:org/api.git\"\nbranch = \"main\""
+            )
+            .unwrap(),
+            manifest
+        );
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].contains("local"));
+    }
+
+    #[test]
+    fn should_skip_non_empty_directory() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("clients/api");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("file.txt"), "content").unwrap();
+        let manifest = parse(
+            "[[project]]\nname = \"clients\"\n[[project.repo]]\nname = \"api\"\nurl = \"file:///none\"",
+        )
+        .unwrap();
+
+        let mut result = Vec::new();
+        assert!(sync(temp_dir.path(), &manifest, &mut result).is_err());
+        assert!(String::from_utf8_lossy(&result).contains("is not a Git repository"));
+    }
+
+    #[test]
+    fn should_continue_after_invalid_targets() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("dev");
+        fs::create_dir_all(root.join("clients")).unwrap();
+        fs::write(root.join("clients/file"), "content").unwrap();
+        let origin = temp_dir.path().join("origin");
+        fs::create_dir_all(&origin).unwrap();
+        git::query_cmd(&["init", "-q", "--bare", "-b", "main"], &origin);
+        let manifest = parse(&format!(
+            "[[project]]\nname = \"clients\"\n\
+            [[project.repo]]\nname = \"file\"\nurl = \"file:///none\"\n\
+            [[project.repo]]\nname = \"outside\"\nurl = \"file:///none\"\npath = \"../outside\"\n\
+            [[project.repo]]\nname = \"api\"\nurl = {:?}",
+            origin.to_str().unwrap()
+        ))
+        .unwrap();
+
+        let mut result = Vec::new();
+        assert!(sync(&root, &manifest, &mut result).is_err());
+        let result = String::from_utf8_lossy(&result);
+        assert!(result.contains("is not a Git repository"), "{}", result);
+        assert!(result.contains("not a relative path inside DEV_DIR"), "{}", result);
+        assert!(root.join("clients/api").join(GIT_DIR).exists(), "{}", result);
+        assert!(!temp_dir.path().join("outside").exists());
+    }
+}
@@ -2,16 +2,114 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use colored::Colorize;
+use strsim::levenshtein;
 
 use crate::ERROR_WRITER;
 use crate::directory::{contains_git, Directory, get_name, read_dirs};
 
+#[derive(Debug)]
 pub struct Project {
     pub name: String,
     pub path: PathBuf,
     pub repos: Option<Vec<Directory>>,
 }
 
+impl Project {
+    /// Path of the project relative to the scanned `root`, joined with '/',
+    /// e.g. `clients/api`. Falls back to the project name for the root itself
+    pub fn qualified_name(&self, root: &Path) -> String {
+        let components = relative_components(&self.path, root);
+        if components.is_empty() {
+            self.name.clone()
+        } else {
+            components.join("/")
+        }
+    }
+
+    /// Name of the `repo` of this project as accepted by selectors, e.g. `clients:api`.
+    /// Unlike the directory name of the repository it is unique within the `root`
+    pub fn repo_name(&self, root: &Path, repo: &Directory) -> String {
+        format!("{}:{}", self.qualified_name(root), repo.name)
+    }
+}
+
+fn relative_components(path: &Path, root: &Path) -> Vec<String> {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Finds exactly one project for given `name`. The name can be qualified with its parent
+/// directories (`clients/api`) to pick one of several projects with the same directory name
+pub fn find_project<'a>(projects: &'a [Project], root: &Path, name: &str) -> Result<&'a Project> {
+    let matches = matching_projects(projects, root, name);
+    match matches.len() {
+        0 => {
+            let mut msg = format!("Project with given name '{}' was not found", name.red());
+            if let Some(suggestion) = suggest(projects, root, name) {
+                msg.push_str(&format!(". Did you mean '{}'?", suggestion.bright_green()));
+            }
+            Err(anyhow!(msg))
+        }
+        1 => Ok(matches[0]),
+        _ => {
+            let mut msg = format!(
+                "Project name '{}' is ambiguous, use one of the qualified names:",
+                name.red()
+            );
+            for project in matches {
+                msg.push_str(&format!(
+                    "\n  - {} {:?}",
+                    project.qualified_name(root).bright_green(),
+                    project.path
+                ));
+            }
+            Err(anyhow!(msg))
+        }
+    }
+}
+
+/// Projects whose path relative to `root` ends with the '/' separated components of `name`
+pub fn matching_projects<'a>(projects: &'a [Project], root: &Path, name: &str) -> Vec<&'a Project> {
+    let wanted: Vec<&str> = name.split('/').filter(|s| !s.is_empty()).collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    projects
+        .iter()
+        .filter(|project| {
+            let mut components = relative_components(&project.path, root);
+            if components.is_empty() {
+                components.push(project.name.clone());
+            }
+            components.len() >= wanted.len()
+                && components[components.len() - wanted.len()..]
+                    .iter()
+                    .zip(&wanted)
+                    .all(|(have, want)| have.eq_ignore_ascii_case(want))
+        })
+        .collect()
+}
+
+fn suggest(projects: &[Project], root: &Path, name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    let max_distance = (name.len() / 3).max(1);
+
+    projects
+        .iter()
+        .flat_map(|p| [p.name.clone(), p.qualified_name(root)])
+        .map(|candidate| {
+            let distance = levenshtein(&name, &candidate.to_lowercase());
+            (candidate, distance)
+        })
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
 pub fn scan(path: &Path) -> Result<Vec<Project>> {
     let dirs = read_dirs(path)?;
     if contains_git(&dirs) {
@@ -182,13 +280,86 @@ mod tests {
         }
     }
 
+    fn make_projects_with_same_name() -> Vec<Project> {
+        vec![
+            Project {
+                name: "api".to_string(),
+                path: PathBuf::from("/dev/clients/api"),
+                repos: None,
+            },
+            Project {
+                name: "api".to_string(),
+                path: PathBuf::from("/dev/internal/api"),
+                repos: None,
+            },
+            Project {
+                name: "frontend".to_string(),
+                path: PathBuf::from("/dev/frontend"),
+                repos: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn should_find_project_by_name() {
+        let projects = make_projects_with_same_name();
+        let result = find_project(&projects, Path::new("/dev"), "FrontEnd").unwrap();
+        assert_eq!(PathBuf::from("/dev/frontend"), result.path);
+    }
+
+    #[test]
+    fn should_find_project_by_qualified_name() {
+        let projects = make_projects_with_same_name();
+        let result = find_project(&projects, Path::new("/dev"), "internal/api").unwrap();
+        assert_eq!(PathBuf::from("/dev/internal/api"), result.path);
+    }
+
+    #[test]
+    fn should_report_ambiguous_name() {
+        let projects = make_projects_with_same_name();
+        let err = find_project(&projects, Path::new("/dev"), "api").unwrap_err().to_string();
+        assert!(err.contains("ambiguous"));
+        assert!(err.contains("clients/api"));
+        assert!(err.contains("internal/api"));
+    }
+
+    #[test]
+    fn should_suggest_similar_name() {
+        let projects = make_projects_with_same_name();
+        let err = find_project(&projects, Path::new("/dev"), "frontnd").unwrap_err().to_string();
+        assert!(err.contains("Did you mean"));
+        assert!(err.contains("frontend"));
+    }
+
+    #[test]
+    fn should_not_suggest_unrelated_name() {
+        let projects = make_projects_with_same_name();
+        let err = find_project(&projects, Path::new("/dev"), "database").unwrap_err().to_string();
+        assert!(err.contains("was not found"));
+        assert!(!err.contains("Did you mean"));
+    }
+
+    #[test]
+    fn test_qualified_name() {
+        let project = make_project_without_repos();
+        assert_eq!("path", project.qualified_name(Path::new("/some")));
+        assert_eq!("Project", project.qualified_name(Path::new("/some/path")));
+    }
+
+    #[test]
+    fn test_repo_name() {
+        let project = make_project_with_one_repo();
+        let repo = &project.repos.as_ref().unwrap()[0];
+        assert_eq!("different/path:DifferentRepo", project.repo_name(Path::new("/some"), repo));
+    }
+
     #[test]
     fn test_parent_lvl_project() {
         let path = Path::new("/some/path/some-name");
         let result = parent_lvl_project(path);
         assert_eq!(1, result.len());
 
-        let res_project = result.into_iter().nth(0).unwrap();
+        let res_project = result.into_iter().next().unwrap();
         assert_eq!("some-name", res_project.name);
         assert_eq!(path, res_project.path);
         assert!(res_project.repos.is_none());
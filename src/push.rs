@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use crate::bulk::Outcome;
+use crate::git;
+
+const ORIGIN: &str = "origin";
+
+/// Pushes the current branch of the repository at `path` when it has commits ahead of
+/// the upstream. Without upstream, the branch is pushed only with `set_upstream`.
+/// Branches for which `is_protected` returns true are never pushed, neither are branches
+/// with a protected upstream, as pushing them would update the protected branch
+pub fn push(path: &Path, set_upstream: bool, is_protected: impl Fn(&str) -> bool) -> Outcome {
+    let branch = match git::current_branch(path) {
+        Ok(branch) if branch.is_empty() => return Outcome::Skipped("HEAD is detached".to_string()),
+        Ok(branch) => branch,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let ahead_behind = match git::ahead_behind(path) {
+        Ok(ahead_behind) => ahead_behind,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let pushing = match ahead_behind {
+        Some((ahead, _)) => ahead > 0,
+        None => set_upstream,
+    };
+    if pushing && is_protected(&branch) {
+        return Outcome::Failed(format!("{} is protected, refusing to push", branch));
+    }
+    let upstream = git::upstream_branch(path, &branch);
+    if let Some(upstream) = upstream.filter(|u| pushing && is_protected(u)) {
+        return Outcome::Failed(format!(
+            "{} pushes to protected {}, refusing to push",
+            branch, upstream
+        ));
+    }
+
+    match ahead_behind {
+        Some((0, _)) => Outcome::Skipped("nothing to push".to_string()),
+        Some((ahead, _)) => match git::push(path, None) {
+            Ok(()) => Outcome::Done(format!("pushed {} commit(s) of {}", ahead, branch)),
+            Err(e) => Outcome::Failed(e.to_string()),
+        },
+        None if !set_upstream => {
+            Outcome::Skipped(format!("{} has no upstream, use --set-upstream", branch))
+        }
+        None => match default_remote(path) {
+            Some(remote) => match git::push(path, Some((&remote, &branch))) {
+                Ok(()) => Outcome::Done(format!(
+                    "pushed {} and set upstream to {}/{}",
+                    branch, remote, branch
+                )),
+                Err(e) => Outcome::Failed(e.to_string()),
+            },
+            None => Outcome::Failed("no remote configured".to_string()),
+        },
+    }
+}
+
+fn default_remote(path: &Path) -> Option<String> {
+    let remotes = git::remotes(path).ok()?;
+    remotes
+        .iter()
+        .find(|r| r.as_str() == ORIGIN)
+        .or(remotes.first())
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(path: &Path) {
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "change"]);
+    }
+
+    /// Bare `origin.git` and its clone `local`
+    fn init_cloned() -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let origin = temp_dir.path().join("origin.git");
+        fs::create_dir_all(&origin).unwrap();
+        git(&origin, &["init", "--bare", "-b", "main"]);
+        git(temp_dir.path(), &["clone", "origin.git", "local"]);
+        let local = temp_dir.path().join("local");
+        git(&local, &["checkout", "-b", "main"]);
+        commit(&local);
+        git(&local, &["push", "-u", "origin", "main"]);
+        (temp_dir, origin, local)
+    }
+
+    #[test]
+    fn should_push_only_when_ahead() {
+        let (_temp_dir, origin, local) = init_cloned();
+
+        assert_eq!(Outcome::Skipped("nothing to push".to_string()), push(&local, false, |_| false));
+
+        commit(&local);
+        assert!(matches!(push(&local, false, |_| false), Outcome::Done(_)));
+        assert_eq!(git(&local, &["rev-parse", "HEAD"]), git(&origin, &["rev-parse", "main"]));
+    }
+
+    #[test]
+    fn should_refuse_protected_branch() {
+        let (_temp_dir, origin, local) = init_cloned();
+        let before = git(&origin, &["rev-parse", "main"]);
+
+        commit(&local);
+        assert!(matches!(push(&local, false, |b| b == "main"), Outcome::Failed(_)));
+        assert_eq!(before, git(&origin, &["rev-parse", "main"]));
+    }
+
+    #[test]
+    fn should_refuse_branch_tracking_protected_branch() {
+        let (_temp_dir, origin, local) = init_cloned();
+        let before = git(&origin, &["rev-parse", "main"]);
+        git(&local, &["checkout", "-b", "feature/x", "--track", "origin/main"]);
+        commit(&local);
+
+        let outcome = push(&local, false, |b| b == "main");
+        assert_eq!(
+            Outcome::Failed("feature/x pushes to protected main, refusing to push".to_string()),
+            outcome
+        );
+        assert_eq!(before, git(&origin, &["rev-parse", "main"]));
+    }
+
+    #[test]
+    fn should_set_upstream_only_when_asked() {
+        let (_temp_dir, origin, local) = init_cloned();
+        git(&local, &["checkout", "-b", "feature/x"]);
+        commit(&local);
+
+        assert!(matches!(push(&local, false, |_| false), Outcome::Skipped(_)));
+        assert!(!git::ref_exists(&origin, "refs/heads/feature/x"));
+
+        assert!(matches!(push(&local, true, |_| false), Outcome::Done(_)));
+        assert!(git::ref_exists(&origin, "refs/heads/feature/x"));
+        assert_eq!(Some((0, 0)), git::ahead_behind(&local).unwrap());
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use crate::bulk::Outcome;
+use crate::git;
+
+/// Fills `{repo}` and `{project}` placeholders of the URL `template`
+pub fn render(template: &str, repo: &str, project: &str) -> String {
+    template.replace("{repo}", repo).replace("{project}", project)
+}
+
+/// Lists remotes of the repository at `path` with their URLs
+pub fn list(path: &Path) -> Outcome {
+    let remotes = match git::remotes(path) {
+        Ok(remotes) if remotes.is_empty() => return Outcome::Skipped("no remotes".to_string()),
+        Ok(remotes) => remotes,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let described: Vec<String> = remotes
+        .iter()
+        .map(|remote| match git::remote_url(path, remote) {
+            Ok(url) => format!("{} {}", remote, url),
+            Err(_) => format!("{} (no URL)", remote),
+        })
+        .collect();
+    Outcome::Done(described.join(", "))
+}
+
+pub fn add(path: &Path, remote: &str, url: &str, dry_run: bool) -> Outcome {
+    if has_remote(path, remote) {
+        return Outcome::Skipped(format!("{} already exists", remote));
+    }
+    if dry_run {
+        return Outcome::Done(format!("would add {} {}", remote, url));
+    }
+    match git::add_remote(path, remote, url) {
+        Ok(()) => Outcome::Done(format!("added {} {}", remote, url)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+pub fn set_url(path: &Path, remote: &str, url: &str, dry_run: bool) -> Outcome {
+    if !has_remote(path, remote) {
+        return Outcome::Skipped(format!("no remote {}", remote));
+    }
+    change_url(path, remote, url, dry_run)
+}
+
+pub fn rename(path: &Path, old: &str, new: &str, dry_run: bool) -> Outcome {
+    if !has_remote(path, old) {
+        return Outcome::Skipped(format!("no remote {}", old));
+    }
+    if has_remote(path, new) {
+        return Outcome::Failed(format!("{} already exists", new));
+    }
+    if dry_run {
+        return Outcome::Done(format!("would rename {} to {}", old, new));
+    }
+    match git::rename_remote(path, old, new) {
+        Ok(()) => Outcome::Done(format!("renamed {} to {}", old, new)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+/// Parses a replacement given as `OLD=NEW`
+pub fn parse_replacement(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((old, new)) if !old.is_empty() => Ok((old.to_string(), new.to_string())),
+        _ => Err(format!("expected OLD=NEW, got '{}'", value)),
+    }
+}
+
+/// Replaces the first matching `OLD` part of every remote URL with `NEW`.
+/// Remotes are changed independently, so a failure is reported along with all changes made
+pub fn rewrite(path: &Path, replacements: &[(String, String)], dry_run: bool) -> Outcome {
+    let remotes = match git::remotes(path) {
+        Ok(remotes) => remotes,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let mut changes = Vec::new();
+    let mut failures = Vec::new();
+    for remote in remotes {
+        let Ok(url) = git::remote_url(path, &remote) else {
+            continue;
+        };
+        let Some((old, new)) = replacements.iter().find(|(old, _)| url.contains(old.as_str()))
+        else {
+            continue;
+        };
+        match change_url(path, &remote, &url.replacen(old.as_str(), new, 1), dry_run) {
+            Outcome::Done(msg) => changes.push(msg),
+            Outcome::Skipped(_) => {}
+            Outcome::Failed(msg) => failures.push(format!("{}: {}", remote, msg)),
+        }
+    }
+
+    match (changes.is_empty(), failures.is_empty()) {
+        (true, true) => Outcome::Skipped("no matching URL".to_string()),
+        (false, true) => Outcome::Done(changes.join(", ")),
+        (true, false) => Outcome::Failed(failures.join(", ")),
+        (false, false) => Outcome::Failed(format!(
+            "{}, while {}",
+            failures.join(", "),
+            changes.join(", ")
+        )),
+    }
+}
+
+fn change_url(path: &Path, remote: &str, url: &str, dry_run: bool) -> Outcome {
+    let old = git::remote_url(path, remote).unwrap_or_default();
+    if old == url {
+        return Outcome::Skipped(format!("{} already points to {}", remote, url));
+    }
+    if dry_run {
+        return Outcome::Done(format!("would change {} from {} to {}", remote, old, url));
+    }
+    match git::set_remote_url(path, remote, url) {
+        Ok(()) => Outcome::Done(format!("changed {} from {} to {}", remote, old, url)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+fn has_remote(path: &Path, remote: &str) -> bool {
+    git::remotes(path).is_ok_and(|remotes| remotes.iter().any(|r| r == remote))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    fn init_repo(url: &str) -> TempDir {
+        let temp_dir = tempdir().unwrap();
+        for args in [vec!["init", "-b", "main"], vec!["remote", "add", "origin", url]] {
+            let output = Command::new("git").args(args).current_dir(temp_dir.path()).output().unwrap();
+            assert!(output.status.success(), "{:?}", output);
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn should_render_url() {
+        assert_eq!(
+            "git@github.com:org/clients-api.git",
+            render("git@github.com:org/{project}-{repo}.git", "api", "clients")
+        );
+    }
+
+    #[test]
+    fn should_parse_replacement() {
+        assert_eq!(
+            Ok(("github.com:old".to_string(), "github.com:new".to_string())),
+            parse_replacement("github.com:old=github.com:new")
+        );
+        assert_eq!(Ok(("a".to_string(), "".to_string())), parse_replacement("a="));
+        assert!(parse_replacement("no-separator").is_err());
+        assert!(parse_replacement("=new").is_err());
+    }
+
+    #[test]
+    fn should_rewrite_urls() {
+        let temp_dir = init_repo("git@github.com:old-org/api.git");
+        let path = temp_dir.path();
+        let replacements = vec![
+            ("gitlab.com".to_string(), "example.com".to_string()),
+            ("github.com:old-org".to_string(), "github.com:new-org".to_string()),
+        ];
+
+        assert_eq!(
+            Outcome::Done("would change origin from git@github.com:old-org/api.git \
+                to git@github.com:new-org/api.git".to_string()),
+            rewrite(path, &replacements, true)
+        );
+        assert_eq!("git@github.com:old-org/api.git", git::remote_url(path, "origin").unwrap());
+
+        assert!(matches!(rewrite(path, &replacements, false), Outcome::Done(_)));
+        assert_eq!("git@github.com:new-org/api.git", git::remote_url(path, "origin").unwrap());
+        assert!(matches!(rewrite(path, &replacements, false), Outcome::Skipped(_)));
+    }
+
+    #[test]
+    fn should_report_changes_made_before_failure() {
+        let temp_dir = init_repo("git@github.com:old-org/api.git");
+        let path = temp_dir.path();
+        for args in [
+            vec!["remote", "add", "mirror", "git@github.com:old-org/api-mirror.git"],
+            // set-url refuses to change a remote with several URLs
+            vec!["config", "--add", "remote.mirror.url", "git@github.com:old-org/api-backup.git"],
+        ] {
+            assert!(git::query_cmd(&args, path).status.success());
+        }
+        let replacements = vec![("old-org".to_string(), "new-org".to_string())];
+
+        let outcome = rewrite(path, &replacements, false);
+        assert!(
+            matches!(&outcome, Outcome::Failed(msg) if msg.starts_with("mirror: ")
+                && msg.contains("changed origin from git@github.com:old-org/api.git")),
+            "{:?}",
+            outcome
+        );
+        assert_eq!("git@github.com:new-org/api.git", git::remote_url(path, "origin").unwrap());
+    }
+
+    #[test]
+    fn should_add_rename_and_set_url() {
+        let temp_dir = init_repo("https://example.com/api.git");
+        let path = temp_dir.path();
+
+        assert!(matches!(add(path, "origin", "https://other.com", false), Outcome::Skipped(_)));
+        assert!(matches!(add(path, "mirror", "https://mirror.com/api.git", false), Outcome::Done(_)));
+        assert!(matches!(rename(path, "mirror", "origin", false), Outcome::Failed(_)));
+        assert!(matches!(rename(path, "mirror", "backup", true), Outcome::Done(_)));
+        assert!(has_remote(path, "mirror"));
+        assert!(matches!(rename(path, "mirror", "backup", false), Outcome::Done(_)));
+        assert!(has_remote(path, "backup"));
+
+        assert!(matches!(set_url(path, "missing", "https://x.com", false), Outcome::Skipped(_)));
+        assert!(matches!(set_url(path, "backup", "https://x.com", false), Outcome::Done(_)));
+        assert_eq!("https://x.com", git::remote_url(path, "backup").unwrap());
+        assert!(matches!(list(path), Outcome::Done(msg) if msg.contains("backup https://x.com")));
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::Write;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::{git, ERROR_WRITER};
+
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    pub file: String,
+    pub line: u32,
+    pub text: String,
+}
+
+/// Lines of tracked files in the repository at `path` matching the `pattern`
+pub fn grep(path: &Path, pattern: &str, ignore_case: bool) -> Result<Vec<Match>, git::GitError> {
+    let mut args = vec!["grep", "-n", "-z", "-I", "--no-color"];
+    if ignore_case {
+        args.push("-i");
+    }
+    args.extend(["-e", pattern]);
+
+    let output = git::query_cmd(&args, path);
+    match output.status.code() {
+        Some(0) => Ok(parse(&String::from_utf8_lossy(&output.stdout))),
+        // git grep exits with 1 when nothing matched
+        Some(1) if output.stderr.is_empty() => Ok(Vec::new()),
+        Some(code) => Err(git::GitError::ReadStateCode("grep".to_string(), code)),
+        None => Err(git::GitError::ReadState("grep".to_string())),
+    }
+}
+
+/// With `-z`, every line is `<file>\0<line>\0<text>`
+fn parse(out: &str) -> Vec<Match> {
+    out.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\0');
+            Some(Match {
+                file: fields.next()?.to_string(),
+                line: fields.next()?.parse().ok()?,
+                text: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Prints matches with file paths prefixed by `prefix`, e.g. the path of the repository
+/// relative to DEV_DIR, the same way as `diff` does
+pub fn print_matches(prefix: &str, matches: &[Match], mut writer: impl Write) {
+    for m in matches {
+        writeln!(
+            writer,
+            "{}{}{}:{}",
+            format!("{}/", prefix).yellow(),
+            m.file.bright_green(),
+            format!(":{}", m.line).cyan(),
+            m.text
+        )
+        .expect(ERROR_WRITER);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    #[test]
+    fn should_parse_matches() {
+        let out = "src/a:b.rs\x0012\x00let x = \"a:b\";\nREADME.md\x001\x00# Title\n";
+        assert_eq!(
+            vec![
+                Match { file: "src/a:b.rs".to_string(), line: 12, text: "let x = \"a:b\";".to_string() },
+                Match { file: "README.md".to_string(), line: 1, text: "# Title".to_string() },
+            ],
+            parse(out)
+        );
+    }
+
+    #[test]
+    fn should_grep_tracked_files() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        fs::write(path.join("tracked.txt"), "first\nTODO: fix\n").unwrap();
+        git(path, &["add", "."]);
+        fs::write(path.join("untracked.txt"), "TODO: ignored").unwrap();
+
+        let matches = grep(path, "todo", true).unwrap();
+        assert_eq!(1, matches.len());
+        assert_eq!(("tracked.txt", 2), (matches[0].file.as_str(), matches[0].line));
+
+        let mut out = Vec::new();
+        print_matches("clients/api", &matches, &mut out);
+        assert_eq!("clients/api/tracked.txt:2:TODO: fix\n", String::from_utf8(out).unwrap());
+
+        assert!(grep(path, "todo", false).unwrap().is_empty());
+        assert!(grep(path, "[", false).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+
+use crate::config::Config;
+use crate::directory::Directory;
+use crate::project::{find_project, Project};
+
+pub const ALL: &str = "all";
+const REPO_SEPARATOR: char = ':';
+
+/// Single entry of a command's selection, e.g. `clients`, `svc-*` or `clients:api`
+#[derive(Debug, PartialEq)]
+pub struct Selector {
+    pub project: String,
+    pub repo: Option<String>,
+}
+
+impl From<&str> for Selector {
+    fn from(value: &str) -> Self {
+        match value.split_once(REPO_SEPARATOR) {
+            Some((project, repo)) => Self {
+                project: project.to_string(),
+                repo: Some(repo.to_string()),
+            },
+            None => Self {
+                project: value.to_string(),
+                repo: None,
+            },
+        }
+    }
+}
+
+enum Selected {
+    Whole,
+    Repos(BTreeSet<usize>),
+}
+
+/// Narrows down scanned `projects` to the ones (and their repositories) chosen by `names`,
+/// without the ones matching `exclude` patterns. Group names from `config` are replaced
+/// with their selectors. Projects keep the order of the scan
+pub fn select(
+    projects: Vec<Project>,
+    root: &Path,
+    config: &Config,
+    names: &[String],
+    exclude: &[String],
+) -> Result<Vec<Project>> {
+    let mut selected: Vec<Option<Selected>> = projects.iter().map(|_| None).collect();
+
+    for name in &config.expand(names) {
+        let selector = Selector::from(name.as_str());
+        for idx in matching_indexes(&projects, root, &selector.project)? {
+            match &selector.repo {
+                None => selected[idx] = Some(Selected::Whole),
+                Some(repo) => {
+                    let repos = matching_repos(&projects[idx], repo);
+                    if repos.is_empty() {
+                        return Err(anyhow!(
+                            "No repository matching '{}' found in project {}",
+                            repo.red(),
+                            projects[idx].qualified_name(root).bright_green()
+                        ));
+                    }
+                    match &mut selected[idx] {
+                        Some(Selected::Whole) => {}
+                        Some(Selected::Repos(set)) => set.extend(repos),
+                        None => selected[idx] = Some(Selected::Repos(repos.into_iter().collect())),
+                    }
+                }
+            }
+        }
+    }
+
+    let excluded: Vec<Selector> = config
+        .expand(exclude)
+        .iter()
+        .map(|e| Selector::from(e.as_str()))
+        .collect();
+
+    let result = projects
+        .into_iter()
+        .zip(selected)
+        .filter_map(|(project, selection)| selection.map(|s| (project, s)))
+        .filter(|(project, _)| {
+            !excluded
+                .iter()
+                .any(|e| e.repo.is_none() && project_matches(project, root, &e.project))
+        })
+        .filter_map(|(project, selection)| {
+            let qualified = project.qualified_name(root);
+            let Project { name, path, repos } = project;
+            let repos = repos.map(|repos| {
+                repos
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(idx, _)| match &selection {
+                        Selected::Whole => true,
+                        Selected::Repos(set) => set.contains(idx),
+                    })
+                    .map(|(_, repo)| repo)
+                    .filter(|repo| !is_excluded(repo, &name, &qualified, &excluded))
+                    .collect::<Vec<Directory>>()
+            });
+            match repos {
+                Some(repos) if repos.is_empty() => None,
+                repos => Some(Project { name, path, repos }),
+            }
+        })
+        .collect();
+    Ok(result)
+}
+
+fn matching_indexes(projects: &[Project], root: &Path, pattern: &str) -> Result<Vec<usize>> {
+    if ALL.eq_ignore_ascii_case(pattern) {
+        return Ok((0..projects.len()).collect());
+    }
+
+    if !is_pattern(pattern) {
+        let found = find_project(projects, root, pattern)?;
+        return Ok(projects
+            .iter()
+            .position(|p| std::ptr::eq(p, found))
+            .into_iter()
+            .collect());
+    }
+
+    let indexes: Vec<usize> = projects
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| project_matches(p, root, pattern))
+        .map(|(idx, _)| idx)
+        .collect();
+    if indexes.is_empty() {
+        return Err(anyhow!("No project matches pattern '{}'", pattern.red()));
+    }
+    Ok(indexes)
+}
+
+fn matching_repos(project: &Project, pattern: &str) -> Vec<usize> {
+    project
+        .repos
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, repo)| wildcard_match(pattern, &repo.name))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+fn project_matches(project: &Project, root: &Path, pattern: &str) -> bool {
+    ALL.eq_ignore_ascii_case(pattern)
+        || wildcard_match(pattern, &project.name)
+        || wildcard_match(pattern, &project.qualified_name(root))
+}
+
+/// Exclusion without a project part (`legacy-*`) applies to repositories in every project
+fn is_excluded(repo: &Directory, project: &str, qualified: &str, excluded: &[Selector]) -> bool {
+    excluded.iter().any(|e| match &e.repo {
+        None => wildcard_match(&e.project, &repo.name),
+        Some(repo_pattern) => {
+            (ALL.eq_ignore_ascii_case(&e.project)
+                || wildcard_match(&e.project, project)
+                || wildcard_match(&e.project, qualified))
+                && wildcard_match(repo_pattern, &repo.name)
+        }
+    })
+}
+
+fn is_pattern(value: &str) -> bool {
+    value.contains(['*', '?'])
+}
+
+/// Case-insensitive match supporting `*` (any sequence) and `?` (any single character)
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn make_projects() -> Vec<Project> {
+        vec![
+            make_project("/dev/svc-auth", &["api", "worker"]),
+            make_project("/dev/svc-billing", &["api", "legacy-api"]),
+            make_project("/dev/frontend", &["web"]),
+        ]
+    }
+
+    fn make_project(path: &str, repos: &[&str]) -> Project {
+        let path = PathBuf::from(path);
+        Project {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            repos: Some(
+                repos
+                    .iter()
+                    .map(|r| Directory::from(path.join(r)))
+                    .collect(),
+            ),
+            path,
+        }
+    }
+
+    fn select_with_defaults(names: &[String], exclude: &[String]) -> Result<Vec<Project>> {
+        select(make_projects(), Path::new("/dev"), &Config::default(), names, exclude)
+    }
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn summary(projects: &[Project]) -> Vec<String> {
+        projects
+            .iter()
+            .flat_map(|p| {
+                p.repos
+                    .iter()
+                    .flatten()
+                    .map(move |r| format!("{}:{}", p.name, r.name))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_parse_selector() {
+        assert_eq!(
+            Selector { project: "clients".to_string(), repo: Some("api".to_string()) },
+            Selector::from("clients:api")
+        );
+        assert_eq!(
+            Selector { project: "svc-*".to_string(), repo: None },
+            Selector::from("svc-*")
+        );
+    }
+
+    #[test]
+    fn should_match_wildcards() {
+        assert!(wildcard_match("svc-*", "svc-auth"));
+        assert!(wildcard_match("SVC-*", "svc-auth"));
+        assert!(wildcard_match("*-api", "legacy-api"));
+        assert!(wildcard_match("a?i", "api"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("s*c*h", "svc-auth"));
+        assert!(!wildcard_match("svc-*", "frontend"));
+        assert!(!wildcard_match("a?i", "apis"));
+    }
+
+    #[test]
+    fn should_select_all() {
+        let result = select_with_defaults(&names(&["all"]), &[]).unwrap();
+        assert_eq!(3, result.len());
+    }
+
+    #[test]
+    fn should_select_several_projects() {
+        let result = select_with_defaults(&names(&["frontend", "svc-auth"]), &[]).unwrap();
+        assert_eq!(vec!["svc-auth:api", "svc-auth:worker", "frontend:web"], summary(&result));
+    }
+
+    #[test]
+    fn should_select_by_glob() {
+        let result = select_with_defaults(&names(&["svc-*"]), &[]).unwrap();
+        assert_eq!(2, result.len());
+    }
+
+    #[test]
+    fn should_select_single_repos() {
+        let result = select_with_defaults(&names(&["svc-*:api", "frontend:web"]), &[]).unwrap();
+        assert_eq!(
+            vec!["svc-auth:api", "svc-billing:api", "frontend:web"],
+            summary(&result)
+        );
+    }
+
+    #[test]
+    fn should_exclude_projects_and_repos() {
+        let exclude = names(&["frontend", "legacy-*", "svc-auth:worker"]);
+        let result = select_with_defaults(&names(&["all"]), &exclude).unwrap();
+        assert_eq!(vec!["svc-auth:api", "svc-billing:api"], summary(&result));
+    }
+
+    #[test]
+    fn should_select_groups() {
+        let config =
+            crate::config::parse("[groups]\nbackend = [\"svc-*:api\"]\nweb = [\"frontend\"]")
+                .unwrap();
+        let result = select(
+            make_projects(),
+            Path::new("/dev"),
+            &config,
+            &names(&["backend", "web"]),
+            &names(&["svc-billing"]),
+        )
+        .unwrap();
+        assert_eq!(vec!["svc-auth:api", "frontend:web"], summary(&result));
+    }
+
+    #[test]
+    fn should_fail_for_unknown_repo() {
+        let result = select_with_defaults(&names(&["frontend:api"]), &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_fail_for_unmatched_pattern() {
+        let result = select_with_defaults(&names(&["db-*"]), &[]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::bulk::{for_each_repo, print_outcome, Outcome, Summary};
+use crate::directory::{is_relative_inside, Directory};
+use crate::git;
+use crate::project::Project;
+
+pub const SNAPSHOTS_DIR: &str = ".please/snapshots";
+const EXTENSION: &str = "toml";
+
+/// Branch and commit of every repository at the time of saving
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Snapshot {
+    /// Seconds since the Unix epoch
+    pub created: u64,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<RepoState>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoState {
+    /// Path of the repository relative to DEV_DIR
+    pub path: String,
+    /// Branch checked out, none for detached HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub commit: String,
+}
+
+pub fn snapshot_path(root: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(anyhow!("Invalid snapshot name '{}'", name));
+    }
+    Ok(root.join(SNAPSHOTS_DIR).join(format!("{}.{}", name, EXTENSION)))
+}
+
+/// Records the state of all repositories of the `projects` as snapshot `name`.
+/// Nothing is written when the state of any repository can't be read, as the snapshot
+/// would be incomplete
+pub fn save(
+    root: &Path,
+    projects: &[Project],
+    name: &str,
+    force: bool,
+    writer: impl Write,
+) -> Result<Summary> {
+    let file = snapshot_path(root, name)?;
+    if file.exists() && !force {
+        return Err(anyhow!("Snapshot '{}' already exists, use --force to overwrite it", name));
+    }
+
+    let mut snapshot = Snapshot {
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        repos: Vec::new(),
+    };
+    let summary = for_each_repo(projects, writer, |_, repo| {
+        let commit = match git::rev_parse(&repo.path, "HEAD") {
+            Ok(commit) => commit,
+            Err(e) => return Outcome::Failed(e.to_string()),
+        };
+        let branch = git::current_branch(&repo.path).ok().filter(|b| !b.is_empty());
+        let outcome = Outcome::Done(format!(
+            "{} at {}",
+            branch.as_deref().unwrap_or("detached HEAD"),
+            &commit[..commit.len().min(7)]
+        ));
+        snapshot.repos.push(RepoState {
+            path: repo
+                .path
+                .strip_prefix(root)
+                .unwrap_or(&repo.path)
+                .to_string_lossy()
+                .to_string(),
+            branch,
+            commit,
+        });
+        outcome
+    });
+
+    if summary.failed > 0 {
+        return Err(anyhow!(
+            "Snapshot '{}' not saved, failed in {} repositories",
+            name,
+            summary.failed
+        ));
+    }
+    snapshot.repos.sort_by(|a, b| a.path.cmp(&b.path));
+    fs::create_dir_all(root.join(SNAPSHOTS_DIR))?;
+    fs::write(&file, toml::to_string(&snapshot)?)
+        .with_context(|| format!("Failed to write snapshot to {:?}", file))?;
+    Ok(summary)
+}
+
+pub fn load(root: &Path, name: &str) -> Result<Snapshot> {
+    let file = snapshot_path(root, name)?;
+    let content = fs::read_to_string(&file)
+        .with_context(|| format!("Snapshot '{}' not found at {:?}", name, file))?;
+    toml::from_str(&content).with_context(|| format!("Invalid snapshot at {:?}", file))
+}
+
+/// Names of saved snapshots, sorted
+pub fn list(root: &Path) -> Result<Vec<String>> {
+    let dir = root.join(SNAPSHOTS_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == EXTENSION))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Checks out every repository of the snapshot to the recorded branch and commit.
+/// When the branch has moved since, the recorded commit is checked out as detached HEAD.
+/// Repositories with paths leading out of `root` are not touched
+pub fn restore(root: &Path, snapshot: &Snapshot, mut writer: impl Write) -> Summary {
+    let mut summary = Summary::default();
+    for state in &snapshot.repos {
+        let repo = Directory {
+            name: state.path.clone(),
+            path: root.join(&state.path),
+        };
+        let outcome = if is_relative_inside(Path::new(&state.path)) {
+            restore_repo(&repo.path, state)
+        } else {
+            Outcome::Failed("not a relative path inside DEV_DIR".to_string())
+        };
+        print_outcome(&repo, &outcome, &mut writer);
+        summary.add(&outcome);
+    }
+    summary
+}
+
+fn restore_repo(path: &Path, state: &RepoState) -> Outcome {
+    if !path.is_dir() {
+        return Outcome::Failed("repository not found".to_string());
+    }
+    match git::is_dirty(path) {
+        Ok(true) => return Outcome::Failed("uncommitted changes, stash them first".to_string()),
+        Err(e) => return Outcome::Failed(e.to_string()),
+        Ok(false) => {}
+    }
+    if git::rev_parse(path, &state.commit).is_err() {
+        return Outcome::Failed(format!("commit {} not found, fetch it first", state.commit));
+    }
+
+    let short = &state.commit[..state.commit.len().min(7)];
+    let current_branch = git::current_branch(path).ok().filter(|b| !b.is_empty());
+    let head = git::rev_parse(path, "HEAD").ok();
+    if current_branch == state.branch && head.as_deref() == Some(state.commit.as_str()) {
+        return Outcome::Skipped("already in the recorded state".to_string());
+    }
+
+    if let Some(branch) = &state.branch {
+        let branch_commit = git::rev_parse(path, &format!("refs/heads/{}", branch)).ok();
+        if branch_commit.as_deref() == Some(state.commit.as_str()) {
+            return match git::checkout_in(path, branch) {
+                Ok(()) => Outcome::Done(format!("{} at {}", branch, short)),
+                Err(e) => Outcome::Failed(e.to_string()),
+            };
+        }
+    }
+
+    match git::checkout_in(path, &state.commit) {
+        Ok(()) => match &state.branch {
+            Some(branch) => Outcome::Done(format!("{} has moved, detached at {}", branch, short)),
+            None => Outcome::Done(format!("detached at {}", short)),
+        },
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use crate::project::scan;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(path: &Path) {
+        git(path, &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+            "commit", "--allow-empty", "-m", "change"]);
+    }
+
+    fn init_repo(path: &Path) {
+        fs::create_dir_all(path).unwrap();
+        git(path, &["init", "-b", "main"]);
+        commit(path);
+    }
+
+    #[test]
+    fn should_reject_invalid_names() {
+        let root = Path::new("/dev");
+        assert!(snapshot_path(root, "bug-123").is_ok());
+        assert!(snapshot_path(root, "").is_err());
+        assert!(snapshot_path(root, "../escape").is_err());
+        assert!(snapshot_path(root, ".hidden").is_err());
+    }
+
+    #[test]
+    fn should_save_and_restore() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let api = root.join("clients/api");
+        let web = root.join("clients/web");
+        init_repo(&api);
+        init_repo(&web);
+        git(&web, &["checkout", "-b", "feature"]);
+        let projects = scan(root).unwrap();
+
+        let summary = save(root, &projects, "bug", false, Vec::new()).unwrap();
+        assert_eq!(2, summary.done);
+        assert!(save(root, &projects, "bug", false, Vec::new()).is_err());
+        assert_eq!(vec!["bug"], list(root).unwrap());
+
+        let snapshot = load(root, "bug").unwrap();
+        assert_eq!(2, snapshot.repos.len());
+        assert_eq!("clients/api", snapshot.repos[0].path);
+
+        let api_commit = git(&api, &["rev-parse", "HEAD"]);
+        commit(&api);
+        git(&web, &["checkout", "main"]);
+
+        let summary = restore(root, &snapshot, Vec::new());
+        assert_eq!(Summary { done: 2, skipped: 0, failed: 0 }, summary);
+        assert_eq!(api_commit, git(&api, &["rev-parse", "HEAD"]));
+        assert_eq!("", git(&api, &["branch", "--show-current"]));
+        assert_eq!("feature", git(&web, &["branch", "--show-current"]));
+
+        let summary = restore(root, &snapshot, Vec::new());
+        assert_eq!(1, summary.skipped);
+    }
+
+    #[test]
+    fn should_not_restore_dirty_repository() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let api = root.join("clients/api");
+        init_repo(&api);
+        let snapshot = Snapshot {
+            created: 0,
+            repos: vec![RepoState {
+                path: "clients/api".to_string(),
+                branch: Some("main".to_string()),
+                commit: git(&api, &["rev-parse", "HEAD"]),
+            }],
+        };
+        fs::write(api.join("new.txt"), "new").unwrap();
+
+        assert_eq!(1, restore(root, &snapshot, Vec::new()).failed);
+    }
+
+    #[test]
+    fn should_not_save_incomplete_snapshot() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        init_repo(&root.join("clients/api"));
+        let unborn = root.join("clients/web");
+        fs::create_dir_all(&unborn).unwrap();
+        git(&unborn, &["init", "-b", "main"]);
+        let projects = scan(root).unwrap();
+
+        let err = save(root, &projects, "bug", false, Vec::new()).unwrap_err();
+        assert!(err.to_string().contains("not saved"), "{}", err);
+        assert!(list(root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_not_restore_outside_root() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("dev");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&root).unwrap();
+        init_repo(&outside);
+        let head = git(&outside, &["rev-parse", "HEAD"]);
+        commit(&outside);
+        let snapshot = Snapshot {
+            created: 0,
+            repos: vec![RepoState { path: "../outside".to_string(), branch: None, commit: head }],
+        };
+
+        assert_eq!(1, restore(&root, &snapshot, Vec::new()).failed);
+        assert_eq!("main", git(&outside, &["branch", "--show-current"]));
+    }
+}
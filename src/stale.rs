@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+
+use crate::bulk::Outcome;
+use crate::git::{self, Branch};
+use crate::ERROR_WRITER;
+
+pub const DEFAULT_AGE_DAYS: u64 = 90;
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Branch with the last commit older than the requested age
+#[derive(Debug)]
+pub struct StaleBranch {
+    pub repo: String,
+    pub path: PathBuf,
+    /// Remote of a remote-tracking branch, none for local branches
+    pub remote: Option<String>,
+    pub branch: Branch,
+    /// Merged into the default branch, the one of the same remote for remote-tracking branches
+    pub merged: bool,
+}
+
+impl StaleBranch {
+    /// Name of the branch without the remote prefix
+    pub fn short_name(&self) -> &str {
+        match &self.remote {
+            Some(remote) => &self.branch.name[remote.len() + 1..],
+            None => &self.branch.name,
+        }
+    }
+}
+
+/// Seconds since the Unix epoch of the moment `days` ago
+pub fn cutoff(days: u64) -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    now - days as i64 * DAY_SECONDS
+}
+
+/// Branches of the repository at `path` last committed to before `cutoff`. The checked out
+/// branch, default branches and branches for which `is_protected` returns true are never
+/// considered stale. Neither are remote-tracking branches which are upstreams of local
+/// branches or have the name of the checked out branch
+pub fn find(
+    path: &Path,
+    repo: &str,
+    cutoff: i64,
+    include_remote: bool,
+    is_protected: impl Fn(&str) -> bool,
+) -> Result<Vec<StaleBranch>, git::GitError> {
+    let default = git::default_branch(path)?;
+    let is_default = |name: &str| {
+        default.as_deref() == Some(name)
+            || git::DEFAULT_BRANCHES.contains(&name)
+            || is_protected(name)
+    };
+    let stale = |branch: &Branch| branch.timestamp < cutoff && !branch.head;
+
+    let local = git::branches(path)?;
+    let merged = match &default {
+        Some(default) => git::merged_branches(path, default).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let mut found: Vec<StaleBranch> = local
+        .iter()
+        .filter(|b| stale(b) && !is_default(&b.name))
+        .map(|branch| StaleBranch {
+            repo: repo.to_string(),
+            path: path.to_path_buf(),
+            remote: None,
+            branch: branch.clone(),
+            merged: merged.contains(&branch.name),
+        })
+        .collect();
+
+    if include_remote {
+        let remotes = git::remotes(path)?;
+        let merged: Vec<String> = match &default {
+            Some(default) => remotes
+                .iter()
+                .flat_map(|remote| {
+                    let target = format!("{}/{}", remote, default);
+                    let own = format!("{}/", remote);
+                    git::merged_remote_branches(path, &target)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(move |b| b.starts_with(&own))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let is_used = |stale: &StaleBranch| {
+            local.iter().any(|b| {
+                b.upstream.as_deref() == Some(stale.branch.name.as_str())
+                    || (b.head && b.name == stale.short_name())
+            })
+        };
+        for branch in git::remote_branches(path)?.into_iter().filter(stale) {
+            let Some(remote) = remote_of(&remotes, &branch.name) else {
+                continue;
+            };
+            let stale_branch = StaleBranch {
+                repo: repo.to_string(),
+                path: path.to_path_buf(),
+                remote: Some(remote.to_string()),
+                merged: merged.contains(&branch.name),
+                branch,
+            };
+            if !is_default(stale_branch.short_name()) && !is_used(&stale_branch) {
+                found.push(stale_branch);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The longest of `remotes` the `name` of a remote-tracking branch starts with
+fn remote_of<'a>(remotes: &'a [String], name: &str) -> Option<&'a str> {
+    remotes
+        .iter()
+        .filter(|remote| name.starts_with(&format!("{}/", remote)))
+        .max_by_key(|remote| remote.len())
+        .map(|remote| remote.as_str())
+}
+
+/// Deletes the stale branch, remote ones on their remote. Unless `force` is set, branches
+/// which are not merged into the default branch are kept
+pub fn delete(stale: &StaleBranch, force: bool) -> Outcome {
+    if !stale.merged && !force {
+        return Outcome::Skipped(format!(
+            "kept {}, it is not merged into the default branch, delete it with --force",
+            stale.branch.name
+        ));
+    }
+    let deleted = match &stale.remote {
+        Some(remote) => git::delete_remote_branch(&stale.path, remote, stale.short_name()),
+        None => git::delete_branch(&stale.path, &stale.branch.name, force),
+    };
+    match deleted {
+        Ok(()) => Outcome::Done(format!("deleted {}", stale.branch.name)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+/// Prints stale branches grouped by repository and author of the last commit
+pub fn print_report(branches: &[StaleBranch], now: i64, mut writer: impl Write) {
+    let mut grouped: BTreeMap<&str, BTreeMap<&str, Vec<&StaleBranch>>> = BTreeMap::new();
+    for stale in branches {
+        grouped
+            .entry(&stale.repo)
+            .or_default()
+            .entry(&stale.branch.author)
+            .or_default()
+            .push(stale);
+    }
+
+    for (repo, authors) in grouped {
+        writeln!(writer, "{}", repo.yellow()).expect(ERROR_WRITER);
+        for (author, branches) in authors {
+            writeln!(writer, "  {}", author).expect(ERROR_WRITER);
+            for stale in branches {
+                writeln!(
+                    writer,
+                    "    {} {} ({} days{})",
+                    stale.branch.name.bright_red(),
+                    stale.branch.date,
+                    (now - stale.branch.timestamp) / DAY_SECONDS,
+                    if stale.merged { "" } else { ", not merged" }
+                )
+                .expect(ERROR_WRITER);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str], timestamp: i64) {
+        let date = format!("{} +0000", timestamp);
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .env("GIT_COMMITTER_DATE", &date)
+            .env("GIT_AUTHOR_DATE", &date)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn commit(path: &Path, author: &str, timestamp: i64) {
+        git(path, &["-c", &format!("user.name={}", author), "-c", "user.email=a@example.com",
+            "commit", "--allow-empty", "-m", "change"], timestamp);
+    }
+
+    #[test]
+    fn should_find_remote_of_branch() {
+        let remotes = vec!["origin".to_string(), "origin/mirror".to_string()];
+        assert_eq!(Some("origin"), remote_of(&remotes, "origin/feature/x"));
+        assert_eq!(Some("origin/mirror"), remote_of(&remotes, "origin/mirror/x"));
+        assert_eq!(None, remote_of(&remotes, "upstream/x"));
+    }
+
+    #[test]
+    fn should_find_and_delete_stale_branches() {
+        let temp_dir = tempdir().unwrap();
+        let origin = temp_dir.path().join("origin");
+        let local = temp_dir.path().join("local");
+        std::fs::create_dir_all(&origin).unwrap();
+        let old = cutoff(DEFAULT_AGE_DAYS * 2);
+        let fresh = cutoff(0);
+        git(&origin, &["init", "-b", "main"], old);
+        commit(&origin, "Jane", old);
+        git(&origin, &["branch", "old-remote"], old);
+        git(&origin, &["branch", "release/1.0"], old);
+        git(&origin, &["branch", "current"], old);
+        git(&origin, &["branch", "old-upstream"], old);
+        git(&origin, &["checkout", "-q", "-b", "old-unmerged-remote"], old);
+        commit(&origin, "Jane", old);
+        git(&origin, &["checkout", "-q", "main"], old);
+        git(temp_dir.path(), &["clone", "-q", "origin", "local"], old);
+
+        git(&local, &["branch", "old-local"], old);
+        git(&local, &["branch", "hotfix/old"], old);
+        git(&local, &["checkout", "-q", "-b", "old-unmerged"], old);
+        commit(&local, "Jane", old);
+        git(&local, &["checkout", "-q", "-b", "fresh", "main"], old);
+        commit(&local, "John", fresh);
+        git(&local, &["branch", "-q", "--set-upstream-to=origin/old-upstream", "fresh"], old);
+        git(&local, &["checkout", "-q", "-b", "current", "old-local"], old);
+
+        let cutoff = cutoff(DEFAULT_AGE_DAYS);
+        let is_protected = |b: &str| b.starts_with("release/") || b.starts_with("hotfix/");
+        let local_only = find(&local, "local", cutoff, false, is_protected).unwrap();
+        let names: Vec<_> = local_only.iter().map(|s| s.branch.name.as_str()).collect();
+        assert_eq!(vec!["old-local", "old-unmerged"], names);
+
+        let all = find(&local, "local", cutoff, true, is_protected).unwrap();
+        let names: Vec<_> = all.iter().map(|s| s.branch.name.as_str()).collect();
+        assert_eq!(
+            vec!["old-local", "old-unmerged", "origin/old-remote", "origin/old-unmerged-remote"],
+            names
+        );
+        assert_eq!("old-remote", all[2].short_name());
+        assert_eq!(vec![true, false, true, false], all.iter().map(|s| s.merged).collect::<Vec<_>>());
+
+        let mut out = Vec::new();
+        print_report(&all, cutoff, &mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Jane"), "{}", out);
+        assert!(out.contains("days, not merged)"), "{}", out);
+
+        let outcomes: Vec<_> = all.iter().map(|s| delete(s, false)).collect();
+        assert!(matches!(&outcomes[0], Outcome::Done(_)));
+        assert!(matches!(&outcomes[1], Outcome::Skipped(msg) if msg.contains("--force")));
+        assert!(matches!(&outcomes[2], Outcome::Done(_)));
+        assert!(matches!(&outcomes[3], Outcome::Skipped(_)));
+        assert!(!git::ref_exists(&local, "refs/heads/old-local"));
+        assert!(git::ref_exists(&local, "refs/heads/old-unmerged"));
+        assert!(!git::ref_exists(&origin, "refs/heads/old-remote"));
+        assert!(git::ref_exists(&origin, "refs/heads/old-unmerged-remote"));
+        assert!(git::ref_exists(&origin, "refs/heads/release/1.0"));
+        assert!(git::ref_exists(&origin, "refs/heads/current"));
+        assert!(git::ref_exists(&origin, "refs/heads/old-upstream"));
+
+        assert!(matches!(delete(&all[1], true), Outcome::Done(_)));
+        assert!(matches!(delete(&all[3], true), Outcome::Done(_)));
+        assert!(!git::ref_exists(&local, "refs/heads/old-unmerged"));
+        assert!(!git::ref_exists(&origin, "refs/heads/old-unmerged-remote"));
+    }
+}
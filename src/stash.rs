@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use crate::bulk::Outcome;
+use crate::git;
+
+pub const DEFAULT_LABEL: &str = "please";
+const MESSAGE_PREFIX: &str = "please:";
+
+/// Message of the stashes created with given `label`
+pub fn message(label: &str) -> String {
+    format!("{}{}", MESSAGE_PREFIX, label)
+}
+
+/// Stashes changes of the repository at `path` when it has any. Refuses when a stash
+/// with the same `label` is left from an earlier run, so `pop` can't mistake one for another
+pub fn push(path: &Path, label: &str) -> Outcome {
+    match git::is_dirty(path) {
+        Ok(false) => return Outcome::Skipped("nothing to stash".to_string()),
+        Err(e) => return Outcome::Failed(e.to_string()),
+        Ok(true) => {}
+    }
+    match find(path, label) {
+        Ok(found) if !found.is_empty() => {
+            return Outcome::Failed(format!(
+                "'{}' already stashed as {}, pop or drop it first",
+                label,
+                found.join(", ")
+            ))
+        }
+        Err(e) => return Outcome::Failed(e.to_string()),
+        Ok(_) => {}
+    }
+
+    match git::stash_push(path, &message(label)) {
+        Ok(()) => Outcome::Done(format!("stashed as '{}'", label)),
+        Err(e) => Outcome::Failed(e.to_string()),
+    }
+}
+
+/// Pops the stash with given `label`, other stashes are left untouched. Refuses when
+/// several stashes have the label, as it's not known which one is wanted.
+/// On conflicts the stash is kept, so it can be applied again after resolving them
+pub fn pop(path: &Path, label: &str) -> Outcome {
+    let reference = match find(path, label) {
+        Ok(found) if found.len() > 1 => {
+            return Outcome::Failed(format!(
+                "{} stashes labelled '{}' ({}), pop the right one with 'git stash pop'",
+                found.len(),
+                label,
+                found.join(", ")
+            ))
+        }
+        Ok(mut found) => match found.pop() {
+            Some(reference) => reference,
+            None => return Outcome::Skipped(format!("no stash labelled '{}'", label)),
+        },
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    match git::stash_pop(path, &reference) {
+        Ok(()) => Outcome::Done(format!("popped '{}'", label)),
+        Err(e) => match git::conflicted_files(path) {
+            Ok(files) if !files.is_empty() => Outcome::Failed(format!(
+                "conflicts in {}, stash kept as {}",
+                files.join(", "),
+                reference
+            )),
+            _ => Outcome::Failed(e.to_string()),
+        },
+    }
+}
+
+/// Lists labels of stashes created by `please` in the repository at `path`
+pub fn list(path: &Path) -> Outcome {
+    let labels: Vec<String> = match git::stash_list(path) {
+        Ok(stashes) => stashes
+            .iter()
+            .filter_map(|(reference, subject)| {
+                label_of(subject).map(|label| format!("{} '{}'", reference, label))
+            })
+            .collect(),
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    if labels.is_empty() {
+        Outcome::Skipped("no stashes".to_string())
+    } else {
+        Outcome::Done(labels.join(", "))
+    }
+}
+
+/// References of all stashes with given `label`, the newest first
+fn find(path: &Path, label: &str) -> Result<Vec<String>, git::GitError> {
+    Ok(git::stash_list(path)?
+        .into_iter()
+        .filter(|(_, subject)| label_of(subject) == Some(label))
+        .map(|(reference, _)| reference)
+        .collect())
+}
+
+/// Subject of a stash is `On <branch>: <message>`
+fn label_of(subject: &str) -> Option<&str> {
+    subject
+        .split_once(": ")
+        .and_then(|(_, message)| message.strip_prefix(MESSAGE_PREFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::process::Command;
+
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    fn git(path: &Path, args: &[&str]) {
+        let output = Command::new("git").args(args).current_dir(path).output().unwrap();
+        assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    }
+
+    fn init_repo() -> TempDir {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path();
+        git(path, &["init", "-b", "main"]);
+        git(path, &["config", "user.name", "Tester"]);
+        git(path, &["config", "user.email", "tester@example.com"]);
+        fs::write(path.join("file.txt"), "initial").unwrap();
+        git(path, &["add", "."]);
+        git(path, &["commit", "-m", "init"]);
+        temp_dir
+    }
+
+    #[test]
+    fn should_parse_label() {
+        assert_eq!(Some("bulk"), label_of("On main: please:bulk"));
+        assert_eq!(Some("a: b"), label_of("On feature/x: please:a: b"));
+        assert_eq!(None, label_of("On main: work in progress"));
+        assert_eq!(None, label_of("WIP on main: 1234567 init"));
+    }
+
+    #[test]
+    fn should_stash_and_pop_by_label() {
+        let temp_dir = init_repo();
+        let path = temp_dir.path();
+
+        assert_eq!(Outcome::Skipped("nothing to stash".to_string()), push(path, "bulk"));
+
+        fs::write(path.join("file.txt"), "mine").unwrap();
+        git(path, &["stash", "push", "-m", "unrelated"]);
+        fs::write(path.join("new.txt"), "untracked").unwrap();
+        assert!(matches!(push(path, "bulk"), Outcome::Done(_)));
+        assert!(!git::is_dirty(path).unwrap());
+
+        assert_eq!(Outcome::Done("stash@{0} 'bulk'".to_string()), list(path));
+        assert!(matches!(pop(path, "other"), Outcome::Skipped(_)));
+        assert!(matches!(pop(path, "bulk"), Outcome::Done(_)));
+        assert!(path.join("new.txt").exists());
+        assert_eq!(1, git::stash_list(path).unwrap().len());
+        assert_eq!(Outcome::Skipped("no stashes".to_string()), list(path));
+    }
+
+    #[test]
+    fn should_refuse_ambiguous_label() {
+        let temp_dir = init_repo();
+        let path = temp_dir.path();
+
+        fs::write(path.join("file.txt"), "earlier run").unwrap();
+        assert!(matches!(push(path, "bulk"), Outcome::Done(_)));
+        fs::write(path.join("file.txt"), "this run").unwrap();
+        assert!(matches!(push(path, "bulk"), Outcome::Failed(msg) if msg.contains("stash@{0}")));
+
+        git(path, &["stash", "push", "-m", &message("bulk")]);
+        match pop(path, "bulk") {
+            Outcome::Failed(msg) => assert!(msg.contains("2 stashes"), "{}", msg),
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        }
+        assert_eq!(2, git::stash_list(path).unwrap().len());
+    }
+
+    #[test]
+    fn should_report_conflicts_and_keep_stash() {
+        let temp_dir = init_repo();
+        let path = temp_dir.path();
+
+        fs::write(path.join("file.txt"), "stashed").unwrap();
+        assert!(matches!(push(path, "bulk"), Outcome::Done(_)));
+        fs::write(path.join("file.txt"), "committed").unwrap();
+        git(path, &["commit", "-am", "conflicting"]);
+
+        match pop(path, "bulk") {
+            Outcome::Failed(msg) => assert!(msg.contains("conflicts in file.txt"), "{}", msg),
+            outcome => panic!("Unexpected outcome {:?}", outcome),
+        }
+        assert_eq!(1, git::stash_list(path).unwrap().len());
+    }
+}
@@ -0,0 +1,189 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+
+use crate::directory::{dir_size, format_size, get_name, work_tree_size, GIT_DIR};
+use crate::project::Project;
+use crate::ERROR_WRITER;
+
+/// Disk usage of a single repository in bytes
+#[derive(Debug, PartialEq)]
+pub struct RepoUsage {
+    pub name: String,
+    pub work_tree: u64,
+    pub git: u64,
+}
+
+impl RepoUsage {
+    /// Measures the repository at `path`. `scan` finds only repositories with a `.git`
+    /// directory, so worktrees and submodules with a `.git` file are never measured
+    pub fn measure(name: &str, path: &Path) -> RepoUsage {
+        RepoUsage {
+            name: name.to_string(),
+            work_tree: work_tree_size(path),
+            git: dir_size(&path.join(GIT_DIR)),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.work_tree + self.git
+    }
+}
+
+/// Disk usage of the repositories of a project, the largest first
+#[derive(Debug)]
+pub struct ProjectUsage {
+    pub name: String,
+    pub path: PathBuf,
+    pub repos: Vec<RepoUsage>,
+}
+
+impl ProjectUsage {
+    pub fn work_tree(&self) -> u64 {
+        self.repos.iter().map(|r| r.work_tree).sum()
+    }
+
+    pub fn git(&self) -> u64 {
+        self.repos.iter().map(|r| r.git).sum()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.work_tree() + self.git()
+    }
+}
+
+/// Measures the repositories found by the scan, a project without repositories
+/// is the repository itself. Projects and their repositories are sorted by size, descending
+pub fn measure(projects: &[Project]) -> Vec<ProjectUsage> {
+    let mut usages: Vec<ProjectUsage> = projects
+        .iter()
+        .map(|project| {
+            let mut repos: Vec<RepoUsage> = match &project.repos {
+                Some(repos) => repos.iter().map(|r| RepoUsage::measure(&r.name, &r.path)).collect(),
+                None => vec![RepoUsage::measure(&get_name(&project.path), &project.path)],
+            };
+            repos.sort_by_key(|r| std::cmp::Reverse(r.total()));
+            ProjectUsage {
+                name: project.name.clone(),
+                path: project.path.clone(),
+                repos,
+            }
+        })
+        .collect();
+    usages.sort_by_key(|p| std::cmp::Reverse(p.total()));
+    usages
+}
+
+pub fn print_usage(usages: &[ProjectUsage], mut writer: impl Write) {
+    let width = usages
+        .iter()
+        .flat_map(|p| p.repos.iter().map(|r| r.name.len()))
+        .max()
+        .unwrap_or_default();
+    for project in usages {
+        writeln!(
+            writer,
+            "\nProject {}, {:?}, {}",
+            project.name.bright_green(),
+            project.path,
+            describe(project.total(), project.work_tree(), project.git())
+        )
+        .expect(ERROR_WRITER);
+        for repo in &project.repos {
+            let name = format!("{:width$}", repo.name, width = width);
+            writeln!(
+                writer,
+                "  - {} {}",
+                name.yellow(),
+                describe(repo.total(), repo.work_tree, repo.git)
+            )
+            .expect(ERROR_WRITER);
+        }
+    }
+
+    let work_tree = usages.iter().map(|p| p.work_tree()).sum();
+    let git = usages.iter().map(|p| p.git()).sum();
+    writeln!(writer, "\nTotal {}", describe(work_tree + git, work_tree, git)).expect(ERROR_WRITER);
+}
+
+fn describe(total: u64, work_tree: u64, git: u64) -> String {
+    format!(
+        "{} (work tree {}, .git {})",
+        format_size(total).bold(),
+        format_size(work_tree),
+        format_size(git)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use crate::directory::Directory;
+
+    use super::*;
+
+    fn make_repo(path: &Path, work_tree: usize, git: usize) -> Directory {
+        fs::create_dir_all(path.join(GIT_DIR)).unwrap();
+        fs::write(path.join("file"), vec![b'x'; work_tree]).unwrap();
+        fs::write(path.join(GIT_DIR).join("pack"), vec![b'x'; git]).unwrap();
+        Directory::from(path.to_path_buf())
+    }
+
+    #[test]
+    fn should_measure_and_sort_descending() {
+        let temp_dir = tempdir().unwrap();
+        let small = temp_dir.path().join("small");
+        let large = temp_dir.path().join("large");
+        let projects = vec![
+            Project {
+                name: "small".to_string(),
+                path: small.clone(),
+                repos: Some(vec![make_repo(&small.join("api"), 10, 5)]),
+            },
+            Project {
+                name: "large".to_string(),
+                path: large.clone(),
+                repos: Some(vec![
+                    make_repo(&large.join("web"), 100, 20),
+                    make_repo(&large.join("db"), 1000, 2000),
+                ]),
+            },
+        ];
+
+        let usages = measure(&projects);
+        assert_eq!(vec!["large", "small"], usages.iter().map(|p| p.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(
+            vec![
+                RepoUsage { name: "db".to_string(), work_tree: 1000, git: 2000 },
+                RepoUsage { name: "web".to_string(), work_tree: 100, git: 20 },
+            ],
+            usages[0].repos
+        );
+        assert_eq!(3120, usages[0].total());
+
+        let mut out = Vec::new();
+        print_usage(&usages, &mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("(work tree 1.1 KiB, .git 2.0 KiB)"), "{}", out);
+        assert!(out.contains("Total"), "{}", out);
+    }
+
+    #[test]
+    fn should_measure_project_being_repository() {
+        let temp_dir = tempdir().unwrap();
+        make_repo(temp_dir.path(), 3, 4);
+        let project = Project {
+            name: "repo".to_string(),
+            path: temp_dir.path().to_path_buf(),
+            repos: None,
+        };
+
+        let usages = measure(&[project]);
+        assert_eq!(3, usages[0].repos[0].work_tree);
+        assert_eq!(4, usages[0].repos[0].git);
+    }
+}
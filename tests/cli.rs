@@ -1,5 +1,5 @@
 use assert_cmd::Command;
-use predicates::prelude::predicate;
+use predicates::prelude::{predicate, PredicateBooleanExt};
 
 #[test]
 fn dev_dir_not_defined() -> Result<(), Box<dyn std::error::Error>> {
@@ -33,3 +33,126 @@ fn no_command_given() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn status_for_selected_repos() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    for repo in ["svc-a/first", "svc-a/second", "svc-b/third"] {
+        let repo_path = temp_dir.path().join(repo);
+        std::fs::create_dir_all(&repo_path)?;
+        std::process::Command::new("git").arg("init").current_dir(&repo_path).output()?;
+    }
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.arg("--path").arg(temp_dir.path())
+        .args(["status", "svc-*", "--exclude", "second"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("first"))
+        .stdout(predicate::str::contains("third"))
+        .stdout(predicate::str::contains("second").not());
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.arg("--path").arg(temp_dir.path()).args(["status", "svc-c"]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Did you mean"));
+    Ok(())
+}
+
+fn git(path: &std::path::Path, args: &[&str]) {
+    let output = std::process::Command::new("git").args(args).current_dir(path).output().unwrap();
+    assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+}
+
+/// Bare repository with a single commit on `main`
+fn init_bare(path: &std::path::Path) {
+    let work = tempfile::tempdir().unwrap();
+    git(work.path(), &["init", "-b", "main"]);
+    git(work.path(), &["-c", "user.name=Tester", "-c", "user.email=tester@example.com",
+        "commit", "--allow-empty", "-m", "init"]);
+    git(work.path(), &["clone", "--bare", ".", path.to_str().unwrap()]);
+}
+
+#[test]
+fn sync_clones_missing_repositories() -> Result<(), Box<dyn std::error::Error>> {
+    let remotes = tempfile::tempdir()?;
+    let dev_dir = tempfile::tempdir()?;
+    init_bare(&remotes.path().join("api.git"));
+    init_bare(&remotes.path().join("web.git"));
+
+    std::fs::create_dir_all(dev_dir.path().join("clients/legacy"))?;
+    git(&dev_dir.path().join("clients/legacy"), &["init"]);
+
+    let manifest = dev_dir.path().join("please-manifest.toml");
+    std::fs::write(&manifest, format!(
+        "[[project]]\nname = \"clients\"\n\
+        [[project.repo]]\nname = \"api\"\nurl = \"file://{0}/api.git\"\nbranch = \"main\"\n\
+        [[project.repo]]\nname = \"web\"\nurl = \"file://{0}/web.git\"\n",
+        remotes.path().display()
+    ))?;
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.arg("--path").arg(dev_dir.path()).arg("sync");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("cloned into"))
+        .stdout(predicate::str::contains("legacy"))
+        .stdout(predicate::str::contains("is not declared in the manifest"));
+    assert!(dev_dir.path().join("clients/api/.git").exists());
+    assert!(dev_dir.path().join("clients/web/.git").exists());
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.arg("--path").arg(dev_dir.path()).arg("sync").arg("--manifest").arg(&manifest);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("already present"))
+        .stdout(predicate::str::contains("cloned into").not());
+    Ok(())
+}
+
+#[test]
+fn doctor_checks_filtered_repositories() -> Result<(), Box<dyn std::error::Error>> {
+    let dev_dir = tempfile::tempdir()?;
+    for repo in ["clients/tidy", "clients/changed"] {
+        let repo_path = dev_dir.path().join(repo);
+        std::fs::create_dir_all(&repo_path)?;
+        git(&repo_path, &["init", "-b", "main"]);
+    }
+    std::fs::write(dev_dir.path().join("clients/changed/new.txt"), "new")?;
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.arg("--path").arg(dev_dir.path()).args(["doctor", "--dirty"]);
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("changed: Problem"))
+        .stdout(predicate::str::contains("tidy").not());
+    Ok(())
+}
+
+#[test]
+fn doctor_reports_undefined_dev_dir() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.env_remove("DEV_DIR").arg("doctor");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("DEV_DIR is not defined"))
+        .stdout(predicate::str::contains("export DEV_DIR=<dir> or pass --path"));
+    Ok(())
+}
+
+#[test]
+fn doctor_reports_missing_git() -> Result<(), Box<dyn std::error::Error>> {
+    let dev_dir = tempfile::tempdir()?;
+    std::fs::create_dir_all(dev_dir.path().join("clients/api/.git"))?;
+
+    let mut cmd = Command::cargo_bin("please")?;
+    cmd.env("PATH", "/nonexistent").arg("--path").arg(dev_dir.path()).arg("doctor");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("git executable not found"))
+        .stdout(predicate::str::contains("Skipping checks of repositories"))
+        .stderr(predicate::str::contains("panicked").not());
+    Ok(())
+}